@@ -17,7 +17,7 @@ for file in glob(pattern):
         print(f"Failed to query file '{file}': {res.stderr}")
         exit(1)
     meta = loads(res.stdout)
-    if not meta["author"]: continue
+    if not meta["authors"]: continue
     posts[file] = meta
 
 tmp = ""
@@ -28,7 +28,7 @@ posts_template = ""
 for file, meta in posts.items():
     posts_template += f'<a href="{file[3:-4]}" class="post-card">'
     posts_template += f'<span class="title">{meta["title"]}</span>'
-    desc = f'{meta["author"]} • {meta["date"]} • {meta["category"]}'
+    desc = f'{", ".join(meta["authors"])} • {meta["date"]} • {meta["category"]}'
     posts_template += f'<span class="description">{desc}</span>'
     posts_template += '</a>'
 
@@ -57,7 +57,7 @@ for file, meta in posts.items():
 for category, posts in posts_by_category.items():
     posts_fmf += f"\n## {category}\n\n"
     for meta in posts:
-        posts_fmf += f'- \\({meta["file"][3:-4]})[{meta["title"]}] by {meta["author"]}\n'
+        posts_fmf += f'- \\({meta["file"][3:-4]})[{meta["title"]}] by {", ".join(meta["authors"])}\n'
 
 with open(posts_out, "w") as f:
     f.write(posts_fmf)
@@ -0,0 +1,382 @@
+use std::ops::{Range, RangeInclusive};
+
+use crate::format::{Alignment, CalloutKind, ListType};
+
+/// Position in source. Lines and columns starts from 1, columns are counted in chars
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    /// Byte offset
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+impl Position {
+    /// Gets position after `v`, where `v` starts at this position
+    pub fn advance(self, v: &str) -> Self {
+        v.chars().fold(
+            Self {
+                offset: self.offset + v.len(),
+                ..self
+            },
+            |pos, c| match c {
+                '\n' => Self {
+                    line: pos.line + 1,
+                    column: 1,
+                    ..pos
+                },
+                _ => Self {
+                    column: pos.column + 1,
+                    ..pos
+                },
+            },
+        )
+    }
+}
+
+/// Source span. `end` points right after the last symbol
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    /// Gets span of `v`, where `v` starts at `start`
+    pub fn of(start: Position, v: &str) -> Self {
+        Self {
+            start,
+            end: start.advance(v),
+        }
+    }
+    /// Gets byte range
+    pub fn range(&self) -> Range<usize> {
+        self.start.offset..self.end.offset
+    }
+}
+
+/// Block node. Blocks are produced by [`Document::parse`](crate::document::Document::parse)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Block<'a> {
+    pub kind: BlockKind<'a>,
+    pub span: Span,
+}
+
+/// Kind of [`Block`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BlockKind<'a> {
+    /// Heading. `level` is a count of `#` symbols, so it can be greater than 6.
+    /// `id` is a slug of heading text, unique in document
+    Heading {
+        level: u8,
+        id: String,
+        content: Vec<Inline<'a>>,
+    },
+    /// Paragraph. Lines are separated by [`InlineKind::SoftBreak`]
+    Paragraph(Vec<Inline<'a>>),
+    /// Ordered or unordered list
+    List {
+        ty: ListType,
+        items: Vec<ListItem<'a>>,
+    },
+    /// Block quote (`> ...`). Contains paragraphs and optional attribution (`> -- ...`)
+    BlockQuote {
+        blocks: Vec<Block<'a>>,
+        attribution: Option<Vec<Inline<'a>>>,
+    },
+    /// Figure: line with a single image and caption lines after it
+    Figure {
+        url: &'a str,
+        alt: Option<&'a str>,
+        caption: Vec<Inline<'a>>,
+    },
+    /// Callout (`:::kind title`, blocks and `:::` line). Can't contain
+    /// footnote definitions
+    Callout {
+        kind: CalloutKind,
+        title: Option<Vec<Inline<'a>>>,
+        blocks: Vec<Block<'a>>,
+    },
+    /// Table. Every row has a cell for every column
+    Table {
+        columns: Vec<Alignment>,
+        header: TableRow<'a>,
+        rows: Vec<TableRow<'a>>,
+    },
+    /// Code block. Every line of contents is formatted separately. Closing
+    /// fence is not shorter than opening one
+    CodeBlock {
+        /// Opening fence: three or more `` ` `` or `~`
+        fence: &'a str,
+        meta: CodeMeta<'a>,
+        lines: Vec<CodeLine<'a>>,
+    },
+    /// Footnote definition (`^label: ...`). Lines are separated by [`InlineKind::SoftBreak`]
+    FootnoteDefinition {
+        label: &'a str,
+        content: Vec<Inline<'a>>,
+    },
+    /// Author card (`/// name / date / category`)
+    AuthorCard(AuthorCard<'a>),
+    /// Author card without exactly three parts. Contains trimmed text after
+    /// `///`. It is not rendered
+    InvalidAuthorCard(&'a str),
+    /// Metadata line (`//! key: value`). Contains trimmed text after `//!`
+    Meta(&'a str),
+}
+
+/// List item. Lines are separated by [`InlineKind::SoftBreak`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListItem<'a> {
+    pub content: Vec<Inline<'a>>,
+    /// Paragraphs, code blocks and nested lists after the content
+    pub blocks: Vec<Block<'a>>,
+    pub span: Span,
+}
+
+/// Table row. Contains cells contents
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableRow<'a> {
+    pub cells: Vec<Vec<Inline<'a>>>,
+    pub span: Span,
+}
+
+/// Line of code block contents
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodeLine<'a> {
+    /// Line as is in source
+    pub raw: &'a str,
+    pub content: Vec<Inline<'a>>,
+    pub span: Span,
+}
+
+/// Code block metadata: text after ```` ``` ````. It is a language name or
+/// `key: value` pairs separated by `,`. Parts without `:` continue the
+/// previous value, so values can contain `,`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CodeMeta<'a> {
+    /// Metadata as is in source, trimmed
+    pub raw: &'a str,
+    /// Language (`lang` or `language`)
+    pub lang: Option<&'a str>,
+    /// File name (`file` or `filename`)
+    pub file: Option<&'a str>,
+    pub title: Option<&'a str>,
+    /// Number of the first line (`start`)
+    pub start: Option<usize>,
+    /// Show line numbers (`lines: true`)
+    pub lines: bool,
+    /// Lines are not formatted, `\` is kept as is (`raw: true`)
+    pub verbatim: bool,
+    /// Lines are diff rows (`diff: true` or `lang: diff`). Language is used
+    /// for highlighting of changed lines
+    pub diff: bool,
+    /// Highlighted lines (`highlight: 3-5, 9`). Numbers count from `start`
+    pub highlight: Vec<RangeInclusive<usize>>,
+}
+
+impl<'a> CodeMeta<'a> {
+    /// Parses metadata. Also returns invalid pairs: pairs without `:`, with
+    /// unknown or repeated keys and with invalid values. They are ignored
+    ///
+    /// ```
+    /// use fmf::ast::CodeMeta;
+    ///
+    /// let (meta, invalid) = CodeMeta::parse("lang: toml, file: scsock.toml, highlight: 2-4,7, foo: 1");
+    /// assert_eq!(meta.lang, Some("toml"));
+    /// assert_eq!(meta.file, Some("scsock.toml"));
+    /// assert_eq!(meta.highlight, [2..=4, 7..=7]);
+    /// assert_eq!(invalid, ["foo: 1"]);
+    ///
+    /// let (meta, _) = CodeMeta::parse("rust");
+    /// assert_eq!(meta.lang, Some("rust"));
+    ///
+    /// let (meta, invalid) = CodeMeta::parse("rust, lines: true, start: 40");
+    /// assert_eq!((meta.lang, meta.lines, meta.start), (Some("rust"), true, Some(40)));
+    /// assert!(invalid.is_empty());
+    /// ```
+    pub fn parse(raw: &'a str) -> (Self, Vec<&'a str>) {
+        let raw = raw.trim();
+        let mut meta = Self {
+            raw,
+            ..Self::default()
+        };
+        let mut invalid = Vec::new();
+        let mut start = 0;
+        // Leading single word is a language
+        let first = raw.split(',').next().unwrap_or_default().trim();
+        if !first.is_empty() && !first.contains(':') && !first.contains(char::is_whitespace) {
+            meta.lang = Some(first);
+            start = raw.find(',').map_or(raw.len(), |idx| idx + 1);
+        }
+
+        let mut pairs: Vec<(usize, usize)> = Vec::new();
+        let pairs_start = start;
+        for end in raw
+            .match_indices(',')
+            .map(|(idx, _)| idx)
+            .filter(|&idx| idx >= pairs_start)
+            .chain([raw.len()])
+        {
+            let part = &raw[start..end];
+            match pairs.last_mut() {
+                _ if part.trim().is_empty() => {}
+                Some((_, last)) if !part.contains(':') => *last = end,
+                _ => pairs.push((start, end)),
+            }
+            start = end + 1;
+        }
+
+        for pair in pairs.into_iter().map(|(start, end)| raw[start..end].trim()) {
+            let Some((key, value)) = pair
+                .split_once(':')
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .filter(|(_, value)| !value.is_empty())
+            else {
+                invalid.push(pair);
+                continue;
+            };
+            let valid = match key {
+                "lang" | "language" if meta.lang.is_none() => {
+                    meta.lang = Some(value);
+                    true
+                }
+                "file" | "filename" if meta.file.is_none() => {
+                    meta.file = Some(value);
+                    true
+                }
+                "title" if meta.title.is_none() => {
+                    meta.title = Some(value);
+                    true
+                }
+                "start" if meta.start.is_none() => {
+                    meta.start = value.parse().ok();
+                    meta.start.is_some()
+                }
+                "lines" if !meta.lines => {
+                    meta.lines = value == "true";
+                    matches!(value, "true" | "false")
+                }
+                "raw" if !meta.verbatim => {
+                    meta.verbatim = value == "true";
+                    matches!(value, "true" | "false")
+                }
+                "diff" if !meta.diff => {
+                    meta.diff = value == "true";
+                    matches!(value, "true" | "false")
+                }
+                "highlight" if meta.highlight.is_empty() => {
+                    let ranges = value
+                        .split([',', ' '])
+                        .filter(|v| !v.is_empty())
+                        .map(line_range);
+                    meta.highlight = ranges.collect::<Option<_>>().unwrap_or_default();
+                    !meta.highlight.is_empty()
+                }
+                _ => false,
+            };
+            if !valid {
+                invalid.push(pair);
+            }
+        }
+        meta.diff |= meta.lang == Some("diff");
+        (meta, invalid)
+    }
+}
+
+/// Parses range of lines (`1-3` or `5`)
+fn line_range(v: &str) -> Option<RangeInclusive<usize>> {
+    let (start, end) = v.split_once('-').unwrap_or((v, v));
+    let range = start.parse().ok()?..=end.parse().ok()?;
+    (!range.is_empty()).then_some(range)
+}
+
+/// Author card. All fields are trimmed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuthorCard<'a> {
+    /// Author name. Several authors are separated by `,` (see [`AuthorCard::names`])
+    pub name: &'a str,
+    pub date: &'a str,
+    pub category: &'a str,
+}
+
+impl<'a> AuthorCard<'a> {
+    /// Gets trimmed author names. Empty names are skipped
+    ///
+    /// ```
+    /// use fmf::ast::AuthorCard;
+    ///
+    /// let card = AuthorCard { name: "fox, ceheki! ,", date: "", category: "" };
+    /// assert_eq!(card.names().collect::<Vec<_>>(), ["fox", "ceheki!"]);
+    /// ```
+    pub fn names(&self) -> impl Iterator<Item = &'a str> {
+        self.name
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    }
+}
+
+/// Heading in document outline. Produced by [`Document::outline`](crate::document::Document::outline)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutlineItem {
+    pub level: u8,
+    /// Heading id (see [`BlockKind::Heading`])
+    pub id: String,
+    /// Heading text without formatting
+    pub title: String,
+    pub span: Span,
+}
+
+/// Inline node. Produced by [`Text::parse`](crate::document::Text::parse)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Inline<'a> {
+    pub kind: InlineKind<'a>,
+    pub span: Span,
+}
+
+/// Kind of [`Inline`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InlineKind<'a> {
+    /// Plain text, not escaped
+    Text(&'a str),
+    /// End of source line inside paragraph or list item
+    SoftBreak,
+    Bold(Vec<Inline<'a>>),
+    Italic(Vec<Inline<'a>>),
+    Code(Vec<Inline<'a>>),
+    /// Strikethrough (`\~[...]`)
+    Strikethrough(Vec<Inline<'a>>),
+    /// Underline (`\+[...]`)
+    Underline(Vec<Inline<'a>>),
+    /// Highlighted text (`\=[...]`)
+    Highlight(Vec<Inline<'a>>),
+    /// Subscript (`\,[...]`)
+    Subscript(Vec<Inline<'a>>),
+    /// Superscript (`\'[...]`)
+    Superscript(Vec<Inline<'a>>),
+    /// Keyboard key (`\<key>`). Key is not formatted
+    Keyboard(&'a str),
+    /// Link. `\(url)` has no text, `\[text]` has no url and `\(url)[text]` has both
+    Link {
+        url: Option<&'a str>,
+        text: Option<Vec<Inline<'a>>>,
+    },
+    /// Image (`\!(url)[alt]`). Alt text is not formatted
+    Image {
+        url: &'a str,
+        alt: Option<&'a str>,
+    },
+    /// Footnote reference (`\^[label]`). Contains label
+    FootnoteReference(&'a str),
+}
@@ -0,0 +1,140 @@
+use std::fmt::Display;
+
+use crate::{ast::Span, format::FormatType};
+
+/// Warning about malformed document. Produced by
+/// [`Document::diagnostics`](crate::document::Document::diagnostics)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub span: Span,
+}
+
+/// Kind of [`Diagnostic`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// Code block is not closed until the end of document
+    UnclosedCodeBlock,
+    /// Inline format is not closed until the end of line. Contains `Inline*`
+    /// format type, like [`FormatType::InlineBold`] or [`FormatType::InlineKeyboard`]
+    UnclosedFormat(FormatType),
+    /// `\(url` or `\[text` is not closed until the end of line
+    UnclosedLink,
+    /// `\!(url` or `\!(url)[alt` is not closed until the end of line
+    UnclosedImage,
+    /// Image has no alt text or it is empty
+    MissingAltText,
+    /// `\^[label` is not closed until the end of line
+    UnclosedFootnote,
+    /// Footnote is referenced, but not defined
+    UndefinedFootnote,
+    /// Footnote is defined more than once. The first definition is used
+    DuplicateFootnote,
+    /// Footnote is defined, but not referenced, so it is not rendered
+    UnusedFootnote,
+    /// Escape (`\`) in code block is not followed by format symbol, so it is
+    /// removed. Use `raw: true` code block to keep it
+    SwallowedEscape,
+    /// Code block metadata pair is not `key: value` with known key and valid
+    /// value. It is ignored
+    InvalidCodeMeta,
+    /// Callout is not closed by `:::` line until the end of document
+    UnclosedCallout,
+    /// Callout kind is not one of `note`, `tip`, `warning` or `danger`. It is rendered as note
+    UnknownCallout,
+    /// Metadata line is not empty and is not `//! key: value`
+    InvalidMetadata,
+    /// Metadata key is set more than once. Contains key. The first value is used
+    DuplicateMetadata(String),
+    /// Included document can't be loaded. Contains error
+    IncludeFailed(String),
+    /// Document includes itself directly or through other documents
+    IncludeCycle,
+    /// Includes are nested deeper than [`MAX_DEPTH`](crate::include::MAX_DEPTH)
+    IncludeTooDeep,
+    /// Author card does not contain exactly three parts. Contains count of parts
+    InvalidAuthorCard(usize),
+    /// Heading has more than 6 `#`. Contains heading level
+    HeadingTooDeep(u8),
+    /// Table row has more cells than table columns. Extra cells are dropped
+    TooManyTableCells { columns: usize, found: usize },
+}
+
+impl DiagnosticKind {
+    /// Checks if malformed part is not kept in syntax tree, so
+    /// [`Fmf`](crate::serialize::Fmf) would lose it
+    pub fn is_lossy(&self) -> bool {
+        matches!(self, Self::UnknownCallout | Self::TooManyTableCells { .. })
+    }
+}
+
+impl Display for DiagnosticKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnclosedCodeBlock => f.write_str("code block is not closed"),
+            Self::UnclosedFormat(ty) => {
+                let symbol = match ty {
+                    FormatType::InlineBold => "*",
+                    FormatType::InlineItalic => "_",
+                    FormatType::InlineStrikethrough => "\\~[",
+                    FormatType::InlineUnderline => "\\+[",
+                    FormatType::InlineHighlight => "\\=[",
+                    FormatType::InlineSubscript => "\\,[",
+                    FormatType::InlineSuperscript => "\\'[",
+                    FormatType::InlineKeyboard => "\\<",
+                    _ => "`",
+                };
+                write!(f, "`{symbol}` is not closed")
+            }
+            Self::UnclosedLink => f.write_str("link is not closed"),
+            Self::UnclosedImage => f.write_str("image is not closed"),
+            Self::MissingAltText => f.write_str("image has no alt text"),
+            Self::UnclosedFootnote => f.write_str("footnote reference is not closed"),
+            Self::UndefinedFootnote => f.write_str("footnote is not defined"),
+            Self::DuplicateFootnote => {
+                f.write_str("footnote is already defined, this definition is ignored")
+            }
+            Self::UnusedFootnote => f.write_str("footnote is never referenced, it is not rendered"),
+            Self::UnclosedCallout => f.write_str("callout is not closed"),
+            Self::UnknownCallout => f.write_str(
+                "callout kind should be `note`, `tip`, `warning` or `danger`, rendered as note",
+            ),
+            Self::SwallowedEscape => {
+                f.write_str("`\\` is removed, use `raw: true` code block to keep it")
+            }
+            Self::InvalidCodeMeta => f.write_str("invalid code block metadata"),
+            Self::InvalidMetadata => f.write_str("metadata should be `//! key: value`"),
+            Self::DuplicateMetadata(key) => {
+                write!(f, "metadata `{key}` is already set, this value is ignored")
+            }
+            Self::IncludeFailed(e) => write!(f, "failed to include document: {e}"),
+            Self::IncludeCycle => f.write_str("document includes itself, include is ignored"),
+            Self::IncludeTooDeep => write!(
+                f,
+                "includes are nested deeper than {}, include is ignored",
+                crate::include::MAX_DEPTH
+            ),
+            Self::InvalidAuthorCard(n) => write!(
+                f,
+                "author card should be `/// name / date / category`, found {n} part(s)"
+            ),
+            Self::HeadingTooDeep(n) => {
+                write!(f, "heading level {n} is deeper than 6, rendered as level 6")
+            }
+            Self::TooManyTableCells { columns, found } => write!(
+                f,
+                "table row has {found} cells, but table has {columns} columns, extra cells are dropped"
+            ),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: warning: {}",
+            self.span.start.line, self.span.start.column, self.kind
+        )
+    }
+}
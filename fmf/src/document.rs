@@ -1,11 +1,31 @@
 use std::{
-    fmt::{Display, Write},
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
     marker::PhantomData,
 };
 
-use crate::format::{DefaultFormatEngine, FormatEngine, FormatType, ListType};
+use crate::{
+    ast::{
+        AuthorCard, Block, BlockKind, CodeLine, CodeMeta, Inline, InlineKind, ListItem,
+        OutlineItem, Position, Span, TableRow,
+    },
+    diagnostic::{Diagnostic, DiagnosticKind},
+    format::{Alignment, CalloutKind, DefaultFormatEngine, FormatEngine, FormatType, ListType},
+    parser::{inline_events, push_html, Parser},
+    slug::{plain_text, Slugs},
+};
 
-/// Provides text formatting (inline-formatting)
+/// Provides text formatting (inline-formatting). If format engine has no
+/// escape (like [`ParagraphEngine`](crate::format::ParagraphEngine)), `\\`
+/// makes the next symbol text, and underscores inside of words are text:
+///
+/// ```
+/// use fmf::document::Text;
+///
+/// assert_eq!(Text::text("2\\*3\\*4 \\_x\\_ \\\\").to_string(), "2*3*4 _x_ \\");
+/// assert_eq!(Text::text("snake_case_names, _italic_").to_string(), "snake_case_names, <i>italic</i>");
+/// assert_eq!(Text::text("\\<win>o \\~[typo]").to_string(), "<kbd>win</kbd>o <s>typo</s>");
+/// ```
 pub struct Text<'a, E: FormatEngine = DefaultFormatEngine> {
     pub v: &'a str,
     pub _marker: PhantomData<E>,
@@ -13,6 +33,7 @@ pub struct Text<'a, E: FormatEngine = DefaultFormatEngine> {
 
 impl<'a> Text<'a> {
     #[inline(always)]
+    #[allow(clippy::self_named_constructors)]
     pub fn text(v: &'a str) -> Self {
         Self::new(v)
     }
@@ -25,95 +46,539 @@ impl<'a, E: FormatEngine> Text<'a, E> {
             _marker: PhantomData,
         }
     }
+
+    /// Parses text into inline nodes. Spans are relative to the text start
+    pub fn parse(&self) -> Vec<Inline<'a>> {
+        parse_inline(self.v, E::escape(), Position::default(), &mut Vec::new())
+    }
 }
 
 impl<'a, E: FormatEngine> Display for Text<'a, E> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        #[derive(Default)]
-        struct Inline {
-            bold: bool,
-            italic: bool,
-            code: bool,
-            link: u8,
-        }
-        let mut inline = Inline::default();
-        let exec_inline = |f: &mut std::fmt::Formatter<'_>, v: &mut bool, ty: FormatType| {
-            *v = !*v;
-
-            if !*v {
-                f.write_str(E::end(ty))
-            } else {
-                f.write_str(E::begin(ty))
-            }
-        };
+        push_html::<E>(f, inline_events(self.parse()))
+    }
+}
 
-        let mut lstart = 0;
-        let mut escape = E::escape().is_none();
-        for (idx, c) in self.v.char_indices() {
-            if Some(c) == E::escape() {
-                escape = true;
-                continue;
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Frame<'a> {
+    Bold,
+    Italic,
+    Code,
+    Strikethrough,
+    Underline,
+    Highlight,
+    Subscript,
+    Superscript,
+    Link(Option<&'a str>),
+}
+
+impl Frame<'_> {
+    /// Checks if format is closed by `]`, like link text
+    fn is_bracketed(&self) -> bool {
+        !matches!(self, Self::Bold | Self::Italic | Self::Code)
+    }
+}
+
+/// Open format of [`InlineTree`]
+struct Open<'a> {
+    frame: Frame<'a>,
+    start: Position,
+    /// Span of the symbol that opened format. Kept when format is reopened
+    marker: Span,
+    children: Vec<Inline<'a>>,
+}
+
+/// Builds inline tree. Formats that are closed out of order are closed and
+/// reopened, so the tree is always well-formed.
+#[derive(Default)]
+struct InlineTree<'a> {
+    root: Vec<Inline<'a>>,
+    stack: Vec<Open<'a>>,
+}
+
+impl<'a> InlineTree<'a> {
+    fn push(&mut self, node: Inline<'a>) {
+        match self.stack.last_mut() {
+            Some(open) => open.children.push(node),
+            None => self.root.push(node),
+        }
+    }
+    fn text(&mut self, v: &'a str, start: Position) {
+        if !v.is_empty() {
+            self.push(Inline {
+                kind: InlineKind::Text(v),
+                span: Span::of(start, v),
+            });
+        }
+    }
+    fn open(&mut self, frame: Frame<'a>, marker: Span) {
+        self.stack.push(Open {
+            frame,
+            start: marker.start,
+            marker,
+            children: Vec::new(),
+        });
+    }
+    fn close(&mut self, end: Position) -> Option<Open<'a>> {
+        let Open {
+            frame,
+            start,
+            marker,
+            children,
+        } = self.stack.pop()?;
+        self.push(Inline {
+            kind: match frame {
+                Frame::Bold => InlineKind::Bold(children),
+                Frame::Italic => InlineKind::Italic(children),
+                Frame::Code => InlineKind::Code(children),
+                Frame::Strikethrough => InlineKind::Strikethrough(children),
+                Frame::Underline => InlineKind::Underline(children),
+                Frame::Highlight => InlineKind::Highlight(children),
+                Frame::Subscript => InlineKind::Subscript(children),
+                Frame::Superscript => InlineKind::Superscript(children),
+                Frame::Link(url) => InlineKind::Link {
+                    url,
+                    text: Some(children),
+                },
+            },
+            span: Span { start, end },
+        });
+        Some(Open {
+            frame,
+            start,
+            marker,
+            children: Vec::new(),
+        })
+    }
+    /// Gets depth of format inside of the innermost link or `]`-closed
+    /// format, if it is open
+    fn depth(&self, frame: Frame<'a>) -> Option<usize> {
+        self.stack
+            .iter()
+            .rev()
+            .take_while(|open| !open.frame.is_bracketed())
+            .position(|open| open.frame == frame)
+    }
+    /// Opens or closes format. `marker` is a span of format symbol
+    fn toggle(&mut self, frame: Frame<'a>, marker: Span) {
+        match self.depth(frame) {
+            Some(depth) => {
+                let reopen = (0..depth)
+                    .filter_map(|_| self.close(marker.start))
+                    .collect::<Vec<_>>();
+                self.close(marker.end);
+                for open in reopen.into_iter().rev() {
+                    self.stack.push(Open {
+                        start: marker.end,
+                        ..open
+                    });
+                }
             }
+            None => self.open(frame, marker),
+        }
+    }
+    /// Closes the innermost link or `]`-closed format. Returns `false` if
+    /// there no such format
+    fn close_bracket(&mut self, marker: Span) -> bool {
+        if !self.stack.iter().any(|open| open.frame.is_bracketed()) {
+            return false;
+        }
+        while !self
+            .close(marker.start)
+            .is_some_and(|open| open.frame.is_bracketed())
+        {}
+        // Format is closed at marker start, extend it to marker end
+        if let Some(link) = match self.stack.last_mut() {
+            Some(open) => open.children.last_mut(),
+            None => self.root.last_mut(),
+        } {
+            link.span.end = marker.end;
+        }
+        true
+    }
+    /// Pushes image. Missing alt text is reported to `diagnostics`
+    fn image(
+        &mut self,
+        url: &'a str,
+        alt: Option<&'a str>,
+        span: Span,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        if alt.is_none_or(|alt| alt.trim().is_empty()) {
+            diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::MissingAltText,
+                span,
+            });
+        }
+        self.push(Inline {
+            kind: InlineKind::Image { url, alt },
+            span,
+        });
+    }
+    /// Closes all formats. Unclosed formats are reported to `diagnostics`
+    fn finish(mut self, end: Position, diagnostics: &mut Vec<Diagnostic>) -> Vec<Inline<'a>> {
+        while let Some(open) = self.close(end) {
+            let kind = match open.frame {
+                Frame::Bold => DiagnosticKind::UnclosedFormat(FormatType::InlineBold),
+                Frame::Italic => DiagnosticKind::UnclosedFormat(FormatType::InlineItalic),
+                Frame::Code => DiagnosticKind::UnclosedFormat(FormatType::InlineCode),
+                Frame::Strikethrough => {
+                    DiagnosticKind::UnclosedFormat(FormatType::InlineStrikethrough)
+                }
+                Frame::Underline => DiagnosticKind::UnclosedFormat(FormatType::InlineUnderline),
+                Frame::Highlight => DiagnosticKind::UnclosedFormat(FormatType::InlineHighlight),
+                Frame::Subscript => DiagnosticKind::UnclosedFormat(FormatType::InlineSubscript),
+                Frame::Superscript => DiagnosticKind::UnclosedFormat(FormatType::InlineSuperscript),
+                Frame::Link(_) => DiagnosticKind::UnclosedLink,
+            };
+            diagnostics.push(Diagnostic {
+                kind,
+                span: open.marker,
+            });
+        }
+        self.root
+    }
+}
+
+/// Checks if symbol is a part of word, so `_` near it may be text
+pub(crate) fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Gets the last symbol of text. Symbols escaped by `\\` are returned as `\\`,
+/// so they are never parts of words or whitespace
+fn prev_char(v: &str) -> Option<char> {
+    let c = v.chars().next_back()?;
+    let slashes = v[..v.len() - c.len_utf8()]
+        .bytes()
+        .rev()
+        .take_while(|&b| b == b'\\')
+        .count();
+    match slashes % 2 {
+        0 => Some(c),
+        _ => Some('\\'),
+    }
+}
+
+/// Parses inline-formatting. If `escape` is set, only escaped symbols are
+/// treated as format symbols. `base` is a position of `v` in source.
+///
+/// Without `escape`, `\\` makes the next symbol text, and `_` is italic only
+/// at word boundaries: it opens before non-whitespace if there no word before
+/// it and closes after non-whitespace if there no word after it
+fn parse_inline<'a>(
+    v: &'a str,
+    escape: Option<char>,
+    base: Position,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<Inline<'a>> {
+    /// Link or image
+    enum Link<'a> {
+        None,
+        /// `\` was found. Contains link start
+        Started(usize),
+        /// Inside of `\(...)`. Contains link start and url start
+        Url(usize, usize),
+        /// `\(url)` was found. Contains link start, url and link end
+        Closed(usize, &'a str, usize),
+        /// `\!` was found. Contains image start
+        Bang(usize),
+        /// Inside of `\!(...)`. Contains image start and url start
+        ImageUrl(usize, usize),
+        /// `\!(url)` was found. Contains image start, url and image end
+        ImageClosed(usize, &'a str, usize),
+        /// Inside of `\!(url)[...]`. Contains image start, url and alt start
+        ImageAlt(usize, &'a str, usize),
+        /// `\^` was found. Contains footnote reference start
+        Caret(usize),
+        /// Inside of `\^[...]`. Contains footnote reference start and label start
+        Footnote(usize, usize),
+        /// `\~`, `\+`, `\=`, `\,` or `\'` was found. Contains format start and format
+        Styled(usize, Frame<'a>),
+        /// Inside of `\<...>`. Contains key start and label start
+        Keyboard(usize, usize),
+    }
+    let pos = |idx: usize| base.advance(&v[..idx]);
+    let span = |start: usize, end: usize| Span {
+        start: pos(start),
+        end: pos(end),
+    };
 
-            match c {
-                '&' => f.write_str("&amp;")?,
-                '<' => f.write_str("&lt;")?,
-                '>' => f.write_str("&gt;")?,
+    let mut tree = InlineTree::default();
+    let mut link = Link::None;
+    let mut lstart = 0;
+    let mut escaped = escape.is_none();
 
-                _ if !escape => f.write_char(c)?,
+    for (idx, c) in v.char_indices() {
+        let next = idx + c.len_utf8();
+        if Some(c) == escape {
+            // Escape is removed even if it is not followed by format symbol.
+            // `]` can't close anything here, as brackets are never opened
+            if !v[next..].starts_with(['*', '_', '`']) {
+                diagnostics.push(Diagnostic {
+                    kind: DiagnosticKind::SwallowedEscape,
+                    span: span(idx, next),
+                });
+            }
+            tree.text(&v[lstart..idx], pos(lstart));
+            lstart = next;
+            escaped = true;
+            continue;
+        }
+        if !escaped {
+            continue;
+        }
+        escaped = escape.is_none();
 
-                '\\' if inline.link == 0 => {
-                    inline.link = 1;
+        match link {
+            Link::Url(start, url) => {
+                if c == ')' {
+                    link = Link::Closed(start, &v[url..idx], next);
+                    lstart = next;
                 }
-                '(' if inline.link == 1 => {
-                    f.write_str(E::begin(FormatType::InlineLink))?;
-                    f.write_str(E::begin(FormatType::InlineLinkUrl))?;
-                    inline.link = 2;
-                    lstart = idx + 1;
+                continue;
+            }
+            Link::ImageUrl(start, url) => {
+                if c == ')' {
+                    link = Link::ImageClosed(start, &v[url..idx], next);
+                    lstart = next;
                 }
-                '[' if inline.link == 1 => {
-                    f.write_str(E::begin(FormatType::InlineLink))?;
-                    f.write_str(E::begin(FormatType::InlineLinkText))?;
-                    inline.link = 4;
+                continue;
+            }
+            Link::ImageAlt(start, url, alt) => {
+                if c == ']' {
+                    tree.image(url, Some(&v[alt..idx]), span(start, next), diagnostics);
+                    link = Link::None;
+                    lstart = next;
                 }
-                _ if inline.link == 1 => {
-                    f.write_char(c)?;
-                    inline.link = 0;
+                continue;
+            }
+            Link::Footnote(start, label) => {
+                if c == ']' {
+                    tree.push(Inline {
+                        kind: InlineKind::FootnoteReference(&v[label..idx]),
+                        span: span(start, next),
+                    });
+                    link = Link::None;
+                    lstart = next;
                 }
-                ')' if inline.link == 2 => {
-                    f.write_str(E::end(FormatType::InlineLinkUrl))?;
-                    inline.link = 3;
+                continue;
+            }
+            Link::Keyboard(start, key) => {
+                if c == '>' {
+                    tree.push(Inline {
+                        kind: InlineKind::Keyboard(&v[key..idx]),
+                        span: span(start, next),
+                    });
+                    link = Link::None;
+                    lstart = next;
                 }
-                '[' if inline.link == 3 => {
-                    f.write_str(E::begin(FormatType::InlineLinkText))?;
-                    inline.link = 4;
+                continue;
+            }
+            Link::Started(start) => {
+                link = Link::None;
+                let styled = match c {
+                    '~' => Some(Frame::Strikethrough),
+                    '+' => Some(Frame::Underline),
+                    '=' => Some(Frame::Highlight),
+                    ',' => Some(Frame::Subscript),
+                    '\'' => Some(Frame::Superscript),
+                    _ => None,
+                };
+                // Kept as text if it is not followed by `[`
+                if let Some(frame) = styled {
+                    link = Link::Styled(start, frame);
+                    lstart = idx;
+                    continue;
                 }
-                _ if inline.link == 3 => {
-                    f.write_str(E::begin(FormatType::InlineLinkText))?;
-                    f.write_str(&self.v[lstart..idx - 1])?;
-                    f.write_str(E::end(FormatType::InlineLinkText))?;
-                    f.write_str(E::end(FormatType::InlineLink))?;
-                    f.write_char(c)?;
-                    inline.link = 0;
+                match c {
+                    '<' => {
+                        link = Link::Keyboard(start, next);
+                        lstart = next;
+                    }
+                    '(' => {
+                        link = Link::Url(start, next);
+                        lstart = next;
+                    }
+                    // Kept as text if it is not an image
+                    '!' => {
+                        link = Link::Bang(start);
+                        lstart = idx;
+                    }
+                    // Kept as text if it is not a footnote reference
+                    '^' => {
+                        link = Link::Caret(start);
+                        lstart = idx;
+                    }
+                    '[' => {
+                        tree.open(Frame::Link(None), span(start, next));
+                        lstart = next;
+                    }
+                    // Escaped symbol, keep it as text
+                    _ => lstart = idx,
                 }
-                ']' if inline.link == 4 => {
-                    f.write_str(E::end(FormatType::InlineLinkText))?;
-                    f.write_str(E::end(FormatType::InlineLink))?;
-                    inline.link = 0;
+                continue;
+            }
+            Link::Closed(start, url, end) => {
+                link = Link::None;
+                if c == '[' {
+                    tree.open(Frame::Link(Some(url)), span(start, next));
+                    lstart = next;
+                    continue;
                 }
+                tree.push(Inline {
+                    kind: InlineKind::Link {
+                        url: Some(url),
+                        text: None,
+                    },
+                    span: span(start, end),
+                });
+            }
+            Link::Bang(start) => {
+                link = Link::None;
+                if c == '(' {
+                    link = Link::ImageUrl(start, next);
+                    lstart = next;
+                    continue;
+                }
+            }
+            Link::ImageClosed(start, url, end) => {
+                link = Link::None;
+                if c == '[' {
+                    link = Link::ImageAlt(start, url, next);
+                    lstart = next;
+                    continue;
+                }
+                tree.image(url, None, span(start, end), diagnostics);
+            }
+            Link::Caret(start) => {
+                link = Link::None;
+                if c == '[' {
+                    link = Link::Footnote(start, next);
+                    lstart = next;
+                    continue;
+                }
+            }
+            Link::Styled(start, frame) => {
+                link = Link::None;
+                if c == '[' {
+                    tree.open(frame, span(start, next));
+                    lstart = next;
+                    continue;
+                }
+            }
+            Link::None => {}
+        }
 
-                '*' => exec_inline(f, &mut inline.bold, FormatType::InlineBold)?,
-                '_' => exec_inline(f, &mut inline.italic, FormatType::InlineItalic)?,
-                '`' => exec_inline(f, &mut inline.code, FormatType::InlineCode)?,
-
-                _ => f.write_char(c)?,
+        let frame = match c {
+            '\\' => {
+                tree.text(&v[lstart..idx], pos(lstart));
+                link = Link::Started(idx);
+                lstart = next;
+                continue;
             }
+            ']' => {
+                tree.text(&v[lstart..idx], pos(lstart));
+                if tree.close_bracket(span(idx, next)) {
+                    lstart = next;
+                } else {
+                    lstart = idx;
+                }
+                continue;
+            }
+            '*' => Frame::Bold,
+            // Underscores inside of words are text: `snake_case`
+            '_' if escape.is_none() => {
+                let (prev, next) = (prev_char(&v[..idx]), v[next..].chars().next());
+                let flanking = match tree.depth(Frame::Italic) {
+                    Some(_) => {
+                        prev.is_some_and(|c| !c.is_whitespace()) && !next.is_some_and(is_word)
+                    }
+                    None => next.is_some_and(|c| !c.is_whitespace()) && !prev.is_some_and(is_word),
+                };
+                match flanking {
+                    true => Frame::Italic,
+                    false => continue,
+                }
+            }
+            '_' => Frame::Italic,
+            '`' => Frame::Code,
+            _ => continue,
+        };
+        tree.text(&v[lstart..idx], pos(lstart));
+        tree.toggle(frame, span(idx, next));
+        lstart = next;
+    }
 
-            escape = E::escape().is_none();
+    match link {
+        Link::Url(start, url) => {
+            tree.push(Inline {
+                kind: InlineKind::Link {
+                    url: Some(&v[url..]),
+                    text: None,
+                },
+                span: span(start, v.len()),
+            });
+            diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::UnclosedLink,
+                span: span(start, url),
+            });
         }
-
-        Ok(())
+        Link::Closed(start, url, end) => tree.push(Inline {
+            kind: InlineKind::Link {
+                url: Some(url),
+                text: None,
+            },
+            span: span(start, end),
+        }),
+        Link::ImageUrl(start, url) => {
+            tree.push(Inline {
+                kind: InlineKind::Image {
+                    url: &v[url..],
+                    alt: None,
+                },
+                span: span(start, v.len()),
+            });
+            diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::UnclosedImage,
+                span: span(start, url),
+            });
+        }
+        Link::ImageClosed(start, url, end) => tree.image(url, None, span(start, end), diagnostics),
+        Link::ImageAlt(start, url, alt) => {
+            tree.image(url, Some(&v[alt..]), span(start, v.len()), diagnostics);
+            diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::UnclosedImage,
+                span: span(start, alt),
+            });
+        }
+        Link::Footnote(start, label) => {
+            tree.push(Inline {
+                kind: InlineKind::FootnoteReference(&v[label..]),
+                span: span(start, v.len()),
+            });
+            diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::UnclosedFootnote,
+                span: span(start, label),
+            });
+        }
+        Link::Keyboard(start, key) => {
+            tree.push(Inline {
+                kind: InlineKind::Keyboard(&v[key..]),
+                span: span(start, v.len()),
+            });
+            diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::UnclosedFormat(FormatType::InlineKeyboard),
+                span: span(start, key),
+            });
+        }
+        Link::None | Link::Bang(_) | Link::Caret(_) | Link::Styled(..) => {
+            tree.text(&v[lstart..], pos(lstart))
+        }
+        Link::Started(_) => {}
     }
+
+    tree.finish(pos(v.len()), diagnostics)
 }
 
 /// Provides full document formatting. See module-level docs for more...
@@ -130,8 +595,12 @@ impl<'a, E: FormatEngine> Document<'a, E> {
         }
     }
 
-    /// Gets the first h1 title. If there no title returns empty string
+    /// Gets `title` metadata or the first h1 title. If there no title returns
+    /// empty string
     pub fn get_title(&self) -> &'a str {
+        if let Some(title) = self.metadata().get("title") {
+            return title;
+        }
         self.v
             .lines()
             .map(str::trim)
@@ -140,6 +609,50 @@ impl<'a, E: FormatEngine> Document<'a, E> {
             .next()
             .unwrap_or_default()
     }
+    /// Gets metadata (`//! key: value` lines). The first value of key is used.
+    /// `include` lines are not metadata (see [`include`](crate::include))
+    ///
+    /// ```
+    /// use fmf::{document::Document, format::DefaultFormatEngine};
+    ///
+    /// let doc = Document::<DefaultFormatEngine>::new("//! tags: rust, fmf\n//! draft: true\n# Title");
+    /// let metadata = doc.metadata();
+    /// assert_eq!(metadata["tags"], "rust, fmf");
+    /// assert_eq!(metadata.get("draft"), Some(&"true"));
+    /// ```
+    pub fn metadata(&self) -> BTreeMap<&'a str, &'a str> {
+        let mut metadata = BTreeMap::new();
+        for block in self.blocks() {
+            if let Some((key, value)) = match block.kind {
+                BlockKind::Meta(v) => metadata_entry(v).filter(|&(key, _)| key != "include"),
+                _ => None,
+            } {
+                metadata.entry(key).or_insert(value);
+            }
+        }
+        metadata
+    }
+    /// Gets names of all authors from all author cards in order, without repeats
+    ///
+    /// ```
+    /// use fmf::{document::Document, format::DefaultFormatEngine};
+    ///
+    /// let doc = Document::<DefaultFormatEngine>::new("/// fox, ceheki! / 1 Jan / misc\n\n/// owl, fox / 2 Jan / misc");
+    /// assert_eq!(doc.authors(), ["fox", "ceheki!", "owl"]);
+    /// ```
+    pub fn authors(&self) -> Vec<&'a str> {
+        let mut authors = Vec::new();
+        for block in self.blocks() {
+            if let BlockKind::AuthorCard(card) = block.kind {
+                for name in card.names() {
+                    if !authors.contains(&name) {
+                        authors.push(name);
+                    }
+                }
+            }
+        }
+        authors
+    }
     /// Gets the first author card if any
     pub fn get_author_card(&self) -> Option<(&'a str, &'a str, &'a str)> {
         self.v
@@ -151,165 +664,912 @@ impl<'a, E: FormatEngine> Document<'a, E> {
             .map(|f| (f[0].trim(), f[1].trim(), f[2].trim()))
             .next()
     }
+
+    /// Parses document into syntax tree
+    pub fn parse(&self) -> Vec<Block<'a>> {
+        self.blocks().collect()
+    }
+
+    /// Gets headings of document in order, including headings inside of callouts
+    ///
+    /// ```
+    /// use fmf::{document::Document, format::DefaultFormatEngine};
+    ///
+    /// let outline = Document::<DefaultFormatEngine>::new("# Intro\n## Привет\n## Intro").outline();
+    /// let ids: Vec<_> = outline.iter().map(|h| h.id.as_str()).collect();
+    /// assert_eq!(ids, ["intro", "privet", "intro-1"]);
+    ///
+    /// let outline = Document::<DefaultFormatEngine>::new("# T\n\n:::note\n## Inside\n:::\n\n## Outside").outline();
+    /// let ids: Vec<_> = outline.iter().map(|h| h.id.as_str()).collect();
+    /// assert_eq!(ids, ["t", "inside", "outside"]);
+    /// ```
+    pub fn outline(&self) -> Vec<OutlineItem> {
+        let mut out = Vec::new();
+        self.blocks()
+            .for_each(|block| block_outline(block, &mut out));
+        out
+    }
+
+    /// Gets warnings about malformed parts of document, sorted by position
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut blocks = self.blocks();
+        let mut diagnostics = Vec::new();
+        // References outside of definitions, definitions and references inside of them
+        let mut references = Vec::new();
+        let mut definitions = HashMap::new();
+        let mut keys = HashSet::new();
+        for block in blocks.by_ref() {
+            match block.kind {
+                BlockKind::Meta(v) => {
+                    if let Some((key, _)) =
+                        metadata_entry(v).filter(|&(key, _)| key != "include" && !keys.insert(key))
+                    {
+                        diagnostics.push(Diagnostic {
+                            kind: DiagnosticKind::DuplicateMetadata(key.to_owned()),
+                            span: block.span,
+                        });
+                    }
+                }
+                BlockKind::FootnoteDefinition { label, content } => {
+                    if definitions.contains_key(label) {
+                        diagnostics.push(Diagnostic {
+                            kind: DiagnosticKind::DuplicateFootnote,
+                            span: block.span,
+                        });
+                        continue;
+                    }
+                    let mut inner = Vec::new();
+                    inline_footnotes(&content, &mut inner);
+                    definitions.insert(label, (block.span, inner));
+                }
+                kind => block_footnotes(&kind, &mut references),
+            }
+        }
+        let all = definitions.values().flat_map(|(_, inner)| inner);
+        for &(label, span) in references.iter().chain(all) {
+            if !definitions.contains_key(label) {
+                diagnostics.push(Diagnostic {
+                    kind: DiagnosticKind::UndefinedFootnote,
+                    span,
+                });
+            }
+        }
+        // Definitions are rendered if they are referenced from rendered parts
+        let mut used = HashSet::new();
+        let mut queue = references
+            .iter()
+            .map(|&(label, _)| label)
+            .collect::<Vec<_>>();
+        while let Some(label) = queue.pop() {
+            match definitions.get(label) {
+                Some((_, inner)) if used.insert(label) => {
+                    queue.extend(inner.iter().map(|&(label, _)| label))
+                }
+                _ => {}
+            }
+        }
+        for (label, (span, _)) in &definitions {
+            if !used.contains(label) {
+                diagnostics.push(Diagnostic {
+                    kind: DiagnosticKind::UnusedFootnote,
+                    span: *span,
+                });
+            }
+        }
+        blocks.diagnostics.append(&mut diagnostics);
+        blocks.diagnostics.sort_by_key(|d| d.span.start);
+        blocks.diagnostics
+    }
+
+    /// Gets pull parser over document. See [`Parser`] for more...
+    pub fn parser(&self) -> Parser<'a, E> {
+        Parser::new(self.v)
+    }
+
+    pub(crate) fn blocks(&self) -> Blocks<'a> {
+        Blocks {
+            lines: Lines {
+                v: self.v,
+                pos: Position::default(),
+            },
+            escape: E::escape(),
+            code_escape: <E::CodeblockEngine>::escape(),
+            diagnostics: Vec::new(),
+            slugs: Slugs::default(),
+            nested: false,
+            end: None,
+        }
+    }
 }
 
-struct ASCIIWriter<'a>(&'a str);
-impl<'a> Display for ASCIIWriter<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for fragment in self
-            .0
-            .split(|p| !matches!(p, 'a'..='z' | 'A'..='Z' | '0'..='9' | '_'))
-        {
-            f.write_str(fragment)?;
+/// Collects footnote references with their spans
+fn inline_footnotes<'a>(nodes: &[Inline<'a>], out: &mut Vec<(&'a str, Span)>) {
+    for node in nodes {
+        match &node.kind {
+            InlineKind::FootnoteReference(label) => out.push((label, node.span)),
+            InlineKind::Bold(v)
+            | InlineKind::Italic(v)
+            | InlineKind::Code(v)
+            | InlineKind::Strikethrough(v)
+            | InlineKind::Underline(v)
+            | InlineKind::Highlight(v)
+            | InlineKind::Subscript(v)
+            | InlineKind::Superscript(v) => inline_footnotes(v, out),
+            InlineKind::Link {
+                text: Some(text), ..
+            } => inline_footnotes(text, out),
+            _ => {}
         }
-        Ok(())
     }
 }
 
-impl<'a, E: FormatEngine> Display for Document<'a, E> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        #[derive(PartialEq, Eq)]
-        enum State {
-            Empty,
-            Paragraph,
-            Codeblock,
-            List(ListType),
+/// Collects headings of block and callouts inside of it
+fn block_outline(block: Block<'_>, out: &mut Vec<OutlineItem>) {
+    match block.kind {
+        BlockKind::Heading { level, id, content } => out.push(OutlineItem {
+            level,
+            id,
+            title: plain_text(&content),
+            span: block.span,
+        }),
+        BlockKind::Callout { blocks, .. } => {
+            blocks.into_iter().for_each(|b| block_outline(b, out));
+        }
+        _ => {}
+    }
+}
+
+/// Collects footnote references of block and blocks inside of it
+fn block_footnotes<'a>(kind: &BlockKind<'a>, out: &mut Vec<(&'a str, Span)>) {
+    match kind {
+        BlockKind::Heading { content, .. }
+        | BlockKind::Paragraph(content)
+        | BlockKind::Figure {
+            caption: content, ..
+        }
+        | BlockKind::FootnoteDefinition { content, .. } => inline_footnotes(content, out),
+        BlockKind::List { items, .. } => {
+            for item in items {
+                inline_footnotes(&item.content, out);
+                item.blocks
+                    .iter()
+                    .for_each(|b| block_footnotes(&b.kind, out));
+            }
+        }
+        BlockKind::BlockQuote {
+            blocks,
+            attribution,
+        } => {
+            blocks.iter().for_each(|b| block_footnotes(&b.kind, out));
+            inline_footnotes(attribution.as_deref().unwrap_or_default(), out);
+        }
+        BlockKind::Callout { title, blocks, .. } => {
+            inline_footnotes(title.as_deref().unwrap_or_default(), out);
+            blocks.iter().for_each(|b| block_footnotes(&b.kind, out));
+        }
+        BlockKind::Table { header, rows, .. } => {
+            for row in std::iter::once(header).chain(rows) {
+                row.cells
+                    .iter()
+                    .for_each(|cell| inline_footnotes(cell, out));
+            }
+        }
+        BlockKind::CodeBlock { lines, .. } => {
+            lines
+                .iter()
+                .for_each(|line| inline_footnotes(&line.content, out));
+        }
+        BlockKind::AuthorCard(_) | BlockKind::InvalidAuthorCard(_) | BlockKind::Meta(_) => {}
+    }
+}
+
+/// Source line without line break
+#[derive(Clone, Copy)]
+struct Line<'a> {
+    raw: &'a str,
+    start: Position,
+}
+
+impl<'a> Line<'a> {
+    /// Gets span of `v`. `v` must be a part of the line
+    fn span(&self, v: &str) -> Span {
+        let offset = v.as_ptr() as usize - self.raw.as_ptr() as usize;
+        Span::of(self.start.advance(&self.raw[..offset]), v)
+    }
+}
+
+/// Like [`str::lines`], but tracks line positions
+#[derive(Clone)]
+struct Lines<'a> {
+    v: &'a str,
+    pos: Position,
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Line<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.v.is_empty() {
+            return None;
         }
+        let (line, rest) = self.v.split_once('\n').unwrap_or((self.v, ""));
+        let start = self.pos;
+        self.pos = start.advance(&self.v[..self.v.len() - rest.len()]);
+        self.v = rest;
+
+        Some(Line {
+            raw: line.strip_suffix('\r').unwrap_or(line),
+            start,
+        })
+    }
+}
+
+/// List that is not finished yet
+struct OpenList<'a> {
+    /// Count of whitespace symbols before item markers
+    indent: usize,
+    ty: ListType,
+    items: Vec<ListItem<'a>>,
+    span: Span,
+}
+
+impl<'a> OpenList<'a> {
+    fn finish(self) -> Block<'a> {
+        Block {
+            kind: BlockKind::List {
+                ty: self.ty,
+                items: self.items,
+            },
+            span: self.span,
+        }
+    }
+}
+
+/// Closes the innermost list and moves it into the last item of its parent
+fn close_nested(lists: &mut Vec<OpenList<'_>>) {
+    let list = lists.pop().expect("list is nested");
+    let parent = lists.last_mut().expect("list is nested");
+    let item = parent.items.last_mut().expect("list is not empty");
+    item.span.end = list.span.end;
+    parent.span.end = list.span.end;
+    item.blocks.push(list.finish());
+}
+
+/// Moves block that is open in the innermost item into this item
+fn close_open<'a>(lists: &mut [OpenList<'a>], open: &mut State<'a>) {
+    let Some(block) = std::mem::replace(open, State::Empty).finish() else {
+        return;
+    };
+    let list = lists.last_mut().expect("list is not empty");
+    let item = list.items.last_mut().expect("list is not empty");
+    item.span.end = block.span.end;
+    list.span.end = block.span.end;
+    item.blocks.push(block);
+}
+
+/// Closes open block and nested lists until only `len` lists are open
+fn close_lists<'a>(lists: &mut Vec<OpenList<'a>>, open: &mut State<'a>, len: usize) {
+    close_open(lists, open);
+    while lists.len() > len {
+        close_nested(lists);
+    }
+}
+
+/// Gets index of the list that item with `indent` belongs to: the innermost
+/// list which parent is less indented. Equals to count of lists if item starts
+/// a new nested list
+fn list_level(lists: &[OpenList<'_>], indent: usize) -> usize {
+    lists.iter().take_while(|list| list.indent < indent).count()
+}
+
+/// Gets list type if trimmed `line` is a list item
+fn list_marker(line: &str) -> Option<ListType> {
+    match line.as_bytes().first() {
+        Some(b'-') => Some(ListType::Unordered),
+        Some(b'@') => Some(ListType::Ordered),
+        _ => None,
+    }
+}
+
+/// Gets count of leading whitespace symbols
+fn indent_of(v: &str) -> usize {
+    v.chars().take_while(|c| c.is_whitespace()).count()
+}
+
+/// Removes up to `n` leading whitespace symbols
+fn strip_indent(v: &str, n: usize) -> &str {
+    let len = v
+        .chars()
+        .take(n)
+        .take_while(|c| c.is_whitespace())
+        .map(char::len_utf8)
+        .sum();
+    &v[len..]
+}
+
+/// Splits table row by `|` that is not escaped. Cells are trimmed, outer
+/// `|` are optional
+fn table_cells(line: &str) -> Vec<&str> {
+    let mut cells = Vec::new();
+    let (mut start, mut escaped) = (0, false);
+    for (idx, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '|' => {
+                cells.push(&line[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    let closed = start == line.len() && line.ends_with('|');
+    cells.push(&line[start..]);
+    if line.starts_with('|') {
+        cells.remove(0);
+    }
+    if closed {
+        cells.pop();
+    }
+    cells.into_iter().map(str::trim).collect()
+}
+
+/// Gets label and text of footnote definition (`^label: text`). Label has
+/// no whitespace and brackets
+fn footnote_label(line: &str) -> Option<(&str, &str)> {
+    let (label, text) = line.strip_prefix('^')?.split_once(':')?;
+    match label.is_empty() || label.contains(|c: char| c.is_whitespace() || c == '[' || c == ']') {
+        true => None,
+        false => Some((label, text.trim_start())),
+    }
+}
+
+/// Gets key and value of metadata line contents (`key: value`). Key has no
+/// whitespace, both are trimmed
+pub(crate) fn metadata_entry(v: &str) -> Option<(&str, &str)> {
+    let (key, value) = v.split_once(':')?;
+    let key = key.trim();
+    match key.is_empty() || key.contains(char::is_whitespace) {
+        true => None,
+        false => Some((key, value.trim())),
+    }
+}
+
+/// Gets fence and metadata of code block start line. Fence is three or more
+/// `` ` `` or `~`
+fn code_fence(line: &str) -> Option<(&str, &str)> {
+    let c = line.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let len = line.find(|ch| ch != c).unwrap_or(line.len());
+    (len >= 3).then(|| line.split_at(len))
+}
+
+/// Checks if line closes code block: it contains only fence symbols and is
+/// not shorter than fence
+fn closes_fence(line: &str, fence: &str) -> bool {
+    line.len() >= fence.len() && line.chars().all(|c| fence.starts_with(c))
+}
+
+/// Gets kind name and title of callout start line (`:::kind title`)
+fn callout_start(line: &str) -> Option<(&str, &str)> {
+    let v = line.strip_prefix(":::")?.trim_start();
+    // Missing title is empty and placed at the end of line
+    let (name, title) = v
+        .split_once(char::is_whitespace)
+        .unwrap_or((v, &v[v.len()..]));
+    match name.is_empty() || !name.chars().all(char::is_alphabetic) {
+        true => None,
+        false => Some((name, title.trim_start())),
+    }
+}
+
+/// Gets column alignments if `line` is a table delimiter row (`|---|:-:|`)
+fn table_columns(line: &str) -> Option<Vec<Alignment>> {
+    let cells = table_cells(line);
+    if cells.is_empty() {
+        return None;
+    }
+    cells
+        .into_iter()
+        .map(|cell| {
+            let (left, right) = (cell.starts_with(':'), cell.ends_with(':'));
+            let dashes = cell.strip_prefix(':').unwrap_or(cell);
+            let dashes = dashes.strip_suffix(':').unwrap_or(dashes);
+            if dashes.is_empty() || !dashes.bytes().all(|b| b == b'-') {
+                return None;
+            }
+            Some(match (left, right) {
+                (false, false) => Alignment::None,
+                (true, false) => Alignment::Left,
+                (true, true) => Alignment::Center,
+                (false, true) => Alignment::Right,
+            })
+        })
+        .collect()
+}
+
+enum State<'a> {
+    Empty,
+    Paragraph(Vec<Inline<'a>>, Span),
+    /// Code block. Contains fence, metadata, lines, span and count of
+    /// whitespace symbols removed from the start of lines
+    Codeblock(&'a str, CodeMeta<'a>, Vec<CodeLine<'a>>, Span, usize),
+    /// Open lists, from the outermost to the innermost one, and block that is
+    /// open in the innermost item
+    List(Vec<OpenList<'a>>, Box<State<'a>>),
+    /// Block quote. Contains finished paragraphs and the open one
+    Quote(Vec<Block<'a>>, Box<State<'a>>, Span),
+    Table(Vec<Alignment>, TableRow<'a>, Vec<TableRow<'a>>, Span),
+    /// Figure. Contains image url, alt text and caption
+    Figure(&'a str, Option<&'a str>, Vec<Inline<'a>>, Span),
+    /// Footnote definition. Contains label and text
+    Footnote(&'a str, Vec<Inline<'a>>, Span),
+}
+impl<'a> State<'a> {
+    fn finish(self) -> Option<Block<'a>> {
+        let (kind, span) = match self {
+            State::Empty => return None,
+            State::Paragraph(v, span) => (BlockKind::Paragraph(v), span),
+            State::Codeblock(fence, meta, lines, span, _) => {
+                (BlockKind::CodeBlock { fence, meta, lines }, span)
+            }
+            State::List(mut lists, mut open) => {
+                close_lists(&mut lists, &mut open, 1);
+                return lists.pop().map(OpenList::finish);
+            }
+            State::Quote(mut blocks, open, span) => {
+                blocks.extend(open.finish());
+                (
+                    BlockKind::BlockQuote {
+                        blocks,
+                        attribution: None,
+                    },
+                    span,
+                )
+            }
+            State::Table(columns, header, rows, span) => (
+                BlockKind::Table {
+                    columns,
+                    header,
+                    rows,
+                },
+                span,
+            ),
+            State::Figure(url, alt, caption, span) => {
+                (BlockKind::Figure { url, alt, caption }, span)
+            }
+            State::Footnote(label, content, span) => {
+                (BlockKind::FootnoteDefinition { label, content }, span)
+            }
+        };
+        Some(Block { kind, span })
+    }
+
+    /// Gets start and fence of code block that is not closed yet
+    fn open_codeblock(&self) -> Option<(Position, &'a str)> {
+        match self {
+            State::Codeblock(fence, _, _, span, _) => Some((span.start, fence)),
+            State::List(_, open) => open.open_codeblock(),
+            _ => None,
+        }
+    }
+}
+
+/// Block parser. Reads source lines only until the next block is complete
+pub(crate) struct Blocks<'a> {
+    lines: Lines<'a>,
+    escape: Option<char>,
+    code_escape: Option<char>,
+    diagnostics: Vec<Diagnostic>,
+    slugs: Slugs,
+    /// Blocks are callout contents: they end at `:::` line and footnote
+    /// definitions are not allowed
+    nested: bool,
+    /// `:::` line that closed callout
+    end: Option<Line<'a>>,
+}
+
+impl<'a> Iterator for Blocks<'a> {
+    type Item = Block<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (escape, code_escape) = (self.escape, self.code_escape);
         let mut state = State::Empty;
+        // Blank line was found inside of list
+        let mut blank = false;
+
+        if self.end.is_some() {
+            return None;
+        }
+        while let Some(l) = self.lines.next() {
+            let d = &mut self.diagnostics;
+            let (raw_line, line) = (l.raw, l.raw.trim());
+            let span = l.span(line);
+            let (indent, marker) = (indent_of(raw_line), list_marker(line));
+            let inline = |d: &mut _, v: &'a str| parse_inline(v, escape, l.span(v).start, d);
+            let item = |d: &mut _, v: &'a str| ListItem {
+                content: inline(d, v[1..].trim_start()),
+                blocks: Vec::new(),
+                span,
+            };
+            let code_line = |d: &mut _, meta: &CodeMeta, strip: usize| {
+                let raw = strip_indent(raw_line, strip);
+                let span = l.span(raw);
+                let content = match meta.verbatim {
+                    true if raw.is_empty() => Vec::new(),
+                    true => vec![Inline {
+                        kind: InlineKind::Text(raw),
+                        span,
+                    }],
+                    false => parse_inline(raw, code_escape, span.start, d),
+                };
+                CodeLine { raw, content, span }
+            };
+            let row = |d: &mut Vec<_>, columns: usize| {
+                let mut cells = table_cells(line);
+                if cells.len() > columns {
+                    d.push(Diagnostic {
+                        kind: DiagnosticKind::TooManyTableCells {
+                            columns,
+                            found: cells.len(),
+                        },
+                        span,
+                    });
+                }
+                // Missing cells are empty and placed at the end of line
+                cells.resize(columns, &line[line.len()..]);
+                TableRow {
+                    cells: cells.into_iter().map(|v| inline(d, v)).collect(),
+                    span,
+                }
+            };
+            let code_meta = |d: &mut Vec<_>, v: &'a str| {
+                let (meta, invalid) = CodeMeta::parse(v);
+                d.extend(invalid.into_iter().map(|v| Diagnostic {
+                    kind: DiagnosticKind::InvalidCodeMeta,
+                    span: l.span(v),
+                }));
+                meta
+            };
+            let soft_break = |end: Position| Inline {
+                kind: InlineKind::SoftBreak,
+                span: Span {
+                    start: end,
+                    end: span.start,
+                },
+            };
 
-        for (raw_line, line) in self.v.lines().map(|l| (l, l.trim())) {
-            match state {
-                State::Codeblock if line != "```" => {
-                    writeln!(f, "{}", Text::<E::CodeblockEngine>::new(raw_line))?
-                }
-
-                _ if line.is_empty() => {
-                    match state {
-                        State::Paragraph => f.write_str(E::end(FormatType::Paragraph))?,
-                        State::Codeblock => write!(
-                            f,
-                            "{}{}",
-                            E::end(FormatType::CodeBlockContents),
-                            E::end(FormatType::CodeBlock)
-                        )?,
-                        State::List(l) => write!(
-                            f,
-                            "{}{}",
-                            E::end(FormatType::ListItem(l)),
-                            E::end(FormatType::List(l))
-                        )?,
-                        State::Empty => {}
+            // Callout contents end at `:::` line outside of code blocks
+            if self.nested && line == ":::" && state.open_codeblock().is_none() {
+                self.end = Some(l);
+                return state.finish();
+            }
+            // The first line of quote is handled like the next ones
+            if matches!(state, State::Empty) && line.starts_with('>') {
+                state = State::Quote(Vec::new(), Box::new(State::Empty), span);
+            }
+            // Table starts with header and delimiter row
+            let columns = match state {
+                State::Empty if line.starts_with('|') => self
+                    .lines
+                    .clone()
+                    .next()
+                    .and_then(|l| table_columns(l.raw.trim()))
+                    .filter(|columns| columns.len() == table_cells(line).len()),
+                _ => None,
+            };
+
+            match &mut state {
+                State::Codeblock(fence, meta, lines, block, strip)
+                    if !closes_fence(line, fence) =>
+                {
+                    let line = code_line(d, meta, *strip);
+                    block.end = line.span.end;
+                    lines.push(line);
+                }
+
+                State::List(lists, open) => {
+                    let after_blank = std::mem::take(&mut blank);
+                    let level = list_level(lists, indent);
+
+                    if let State::Codeblock(fence, meta, lines, block, strip) = open.as_mut() {
+                        match closes_fence(line, fence) {
+                            false => {
+                                let line = code_line(d, meta, *strip);
+                                block.end = line.span.end;
+                                lines.push(line);
+                            }
+                            true => {
+                                block.end = span.end;
+                                close_open(lists, open);
+                            }
+                        }
+                    } else if line.is_empty() {
+                        close_open(lists, open);
+                        // List continues if the next line is indented or is its item
+                        let root = &lists[0];
+                        let next = self.lines.clone().find(|l| !l.raw.trim().is_empty());
+                        match next.is_some_and(|l| {
+                            indent_of(l.raw) > root.indent
+                                || list_marker(l.raw.trim()) == Some(root.ty)
+                        }) {
+                            true => blank = true,
+                            false => return state.finish(),
+                        }
+                    } else if let Some(ty) = marker.filter(|&ty| {
+                        // Item with other marker on the same level starts a
+                        // new list after blank line or is continuation text
+                        after_blank || lists.get(level).is_none_or(|list| list.ty == ty)
+                    }) {
+                        match lists.get(level) {
+                            Some(list) if list.ty == ty => close_lists(lists, open, level + 1),
+                            _ => {
+                                close_lists(lists, open, level);
+                                lists.push(OpenList {
+                                    indent,
+                                    ty,
+                                    items: Vec::new(),
+                                    span,
+                                });
+                            }
+                        }
+                        let list = lists.last_mut().expect("list is not empty");
+                        list.items.push(item(d, line));
+                        list.span.end = span.end;
+                    } else if after_blank || code_fence(line).is_some() {
+                        // Paragraphs and code blocks belong to item by indentation.
+                        // Code blocks that are not indented belong to the outermost list
+                        close_lists(lists, open, level.max(1));
+                        **open = match code_fence(line) {
+                            Some((fence, meta)) => State::Codeblock(
+                                fence,
+                                code_meta(d, meta),
+                                Vec::new(),
+                                span,
+                                indent,
+                            ),
+                            None => State::Paragraph(inline(d, line), span),
+                        };
+                    } else {
+                        let list = lists.last_mut().expect("list is not empty");
+                        let item = list.items.last_mut().expect("list is not empty");
+                        match open.as_mut() {
+                            State::Paragraph(content, block) => {
+                                content.push(soft_break(block.end));
+                                content.extend(inline(d, line));
+                                block.end = span.end;
+                            }
+                            _ if item.blocks.is_empty() => {
+                                item.content.push(soft_break(item.span.end));
+                                item.content.extend(inline(d, line));
+                                item.span.end = span.end;
+                                list.span.end = span.end;
+                            }
+                            _ => **open = State::Paragraph(inline(d, line), span),
+                        }
                     }
-                    state = State::Empty;
                 }
 
-                State::Empty if line.starts_with("//!") => {},
+                State::Empty if line.is_empty() => {}
+                _ if line.is_empty() => return state.finish(),
+
+                State::Empty if line.starts_with("//!") => {
+                    let v = line[3..].trim();
+                    if !v.is_empty() && metadata_entry(v).is_none() {
+                        d.push(Diagnostic {
+                            kind: DiagnosticKind::InvalidMetadata,
+                            span,
+                        });
+                    }
+                    return Some(Block {
+                        kind: BlockKind::Meta(v),
+                        span,
+                    });
+                }
 
                 State::Empty if line.starts_with("///") => {
                     let (name, date, category) = match line[3..].split('/').collect::<Vec<_>>() {
                         v if v.len() == 3 => (v[0], v[1], v[2]),
-                        _ => continue,
+                        v => {
+                            d.push(Diagnostic {
+                                kind: DiagnosticKind::InvalidAuthorCard(v.len()),
+                                span,
+                            });
+                            return Some(Block {
+                                kind: BlockKind::InvalidAuthorCard(line[3..].trim()),
+                                span,
+                            });
+                        }
                     };
-                    write!(
-                        f,
-                        "{}{}/{}.jpg{}{}{}{}{}{}{}{}{}{}{}",
-                        E::begin(FormatType::CardAuthor),
-                        E::begin(FormatType::CardAuthorAvatar),
-                        ASCIIWriter(name.trim()),
-                        E::end(FormatType::CardAuthorAvatar),
-                        E::begin(FormatType::CardAuthorName),
-                        name.trim(),
-                        E::end(FormatType::CardAuthorName),
-                        E::begin(FormatType::CardAuthorDate),
-                        date.trim(),
-                        E::end(FormatType::CardAuthorDate),
-                        E::begin(FormatType::CardAuthorCategory),
-                        category.trim(),
-                        E::end(FormatType::CardAuthorCategory),
-                        E::end(FormatType::CardAuthor)
-                    )?;
+                    return Some(Block {
+                        kind: BlockKind::AuthorCard(AuthorCard {
+                            name: name.trim(),
+                            date: date.trim(),
+                            category: category.trim(),
+                        }),
+                        span,
+                    });
                 }
 
                 State::Empty if line.starts_with('#') => {
                     let cnt = line.bytes().take_while(|&f| f == b'#').count();
-                    write!(
-                        f,
-                        "{}{}{}",
-                        E::begin(FormatType::Heading(cnt as u8)),
-                        Text::text(line[cnt..].trim_start()),
-                        E::end(FormatType::Heading(cnt as u8)),
-                    )?;
-                }
-                State::Empty if line.starts_with('-') => {
-                    writeln!(
-                        f,
-                        "{}{}{}",
-                        E::begin(FormatType::List(ListType::Unordered)),
-                        E::begin(FormatType::ListItem(ListType::Unordered)),
-                        Text::text(line[1..].trim_start()),
-                    )?;
-                    state = State::List(ListType::Unordered);
-                }
-                State::Empty if line.starts_with('@') => {
-                    writeln!(
-                        f,
-                        "{}{}{}",
-                        E::begin(FormatType::List(ListType::Ordered)),
-                        E::begin(FormatType::ListItem(ListType::Ordered)),
-                        Text::text(line[1..].trim_start()),
-                    )?;
-                    state = State::List(ListType::Ordered);
-                }
-                State::Empty if line.starts_with("```") => {
-                    write!(
-                        f,
-                        "{}{}{}{}{}",
-                        E::begin(FormatType::CodeBlock),
-                        E::begin(FormatType::CodeBlockMeta),
-                        Text::text(line[3..].trim_start()),
-                        E::end(FormatType::CodeBlockMeta),
-                        E::begin(FormatType::CodeBlockContents),
-                    )?;
-                    state = State::Codeblock;
+                    let level = u8::try_from(cnt).unwrap_or(u8::MAX);
+                    if level > 6 {
+                        d.push(Diagnostic {
+                            kind: DiagnosticKind::HeadingTooDeep(level),
+                            span,
+                        });
+                    }
+                    let content = inline(d, line[cnt..].trim_start());
+                    return Some(Block {
+                        kind: BlockKind::Heading {
+                            level,
+                            id: self.slugs.unique(&plain_text(&content)),
+                            content,
+                        },
+                        span,
+                    });
+                }
+                State::Empty if columns.is_some() => {
+                    let columns = columns.expect("line is table header");
+                    let header = row(d, columns.len());
+                    let delimiter = self.lines.next().expect("delimiter row is found");
+                    let end = delimiter.span(delimiter.raw.trim()).end;
+                    state = State::Table(columns, header, Vec::new(), Span { end, ..span });
+                }
+                State::Empty if code_fence(line).is_some() => {
+                    let (fence, meta) = code_fence(line).expect("line is code block start");
+                    state = State::Codeblock(fence, code_meta(d, meta), Vec::new(), span, 0);
+                }
+                State::Empty if callout_start(line).is_some() => {
+                    let (name, title) = callout_start(line).expect("line is callout start");
+                    let kind = CalloutKind::from_name(name).unwrap_or_else(|| {
+                        d.push(Diagnostic {
+                            kind: DiagnosticKind::UnknownCallout,
+                            span,
+                        });
+                        CalloutKind::Note
+                    });
+                    let title = Some(inline(d, title)).filter(|title| !title.is_empty());
+                    let mut inner = Blocks {
+                        lines: self.lines.clone(),
+                        escape,
+                        code_escape,
+                        diagnostics: Vec::new(),
+                        slugs: std::mem::take(&mut self.slugs),
+                        nested: true,
+                        end: None,
+                    };
+                    let blocks = inner.by_ref().collect::<Vec<_>>();
+                    self.lines = inner.lines;
+                    self.slugs = inner.slugs;
+                    d.append(&mut inner.diagnostics);
+                    let end = match inner.end {
+                        Some(end) => end.span(end.raw.trim()).end,
+                        None => {
+                            d.push(Diagnostic {
+                                kind: DiagnosticKind::UnclosedCallout,
+                                span: Span::of(span.start, ":::"),
+                            });
+                            blocks.last().map_or(span.end, |block| block.span.end)
+                        }
+                    };
+                    return Some(Block {
+                        kind: BlockKind::Callout {
+                            kind,
+                            title,
+                            blocks,
+                        },
+                        span: Span { end, ..span },
+                    });
+                }
+                State::Empty if !self.nested && footnote_label(line).is_some() => {
+                    let (label, text) = footnote_label(line).expect("line is footnote definition");
+                    state = State::Footnote(label, inline(d, text), span);
                 }
                 State::Empty => {
-                    writeln!(f, "{}{}", E::begin(FormatType::Paragraph), Text::text(line))?;
-                    state = State::Paragraph;
+                    state = match marker {
+                        Some(ty) => State::List(
+                            vec![OpenList {
+                                indent,
+                                ty,
+                                items: vec![item(d, line)],
+                                span,
+                            }],
+                            Box::new(State::Empty),
+                        ),
+                        None => {
+                            let nodes = inline(d, line);
+                            match nodes[..] {
+                                // Line with a single image is a figure
+                                [Inline {
+                                    kind: InlineKind::Image { url, alt },
+                                    ..
+                                }] => State::Figure(url, alt, Vec::new(), span),
+                                _ => State::Paragraph(nodes, span),
+                            }
+                        }
+                    }
                 }
 
-                State::List(ListType::Unordered) if line.starts_with('-') => {
-                    writeln!(
-                        f,
-                        "{}{}{}",
-                        E::end(FormatType::ListItem(ListType::Unordered)),
-                        E::begin(FormatType::ListItem(ListType::Unordered)),
-                        Text::text(line[1..].trim_start()),
-                    )?;
+                State::Paragraph(content, block) | State::Footnote(_, content, block) => {
+                    content.push(soft_break(block.end));
+                    content.extend(inline(d, line));
+                    block.end = span.end;
+                }
+                State::Figure(_, _, caption, block) => {
+                    if !caption.is_empty() {
+                        caption.push(soft_break(block.end));
+                    }
+                    caption.extend(inline(d, line));
+                    block.end = span.end;
                 }
-                State::List(ListType::Ordered) if line.starts_with('@') => {
-                    writeln!(
-                        f,
-                        "{}{}{}",
-                        E::end(FormatType::ListItem(ListType::Ordered)),
-                        E::begin(FormatType::ListItem(ListType::Ordered)),
-                        Text::text(line[1..].trim_start()),
-                    )?;
+                State::Table(columns, _, rows, block) => {
+                    rows.push(row(d, columns.len()));
+                    block.end = span.end;
+                }
+                // Lines without `>` continue the last paragraph
+                State::Quote(blocks, open, block) => {
+                    let quoted = line.strip_prefix('>').map(str::trim_start);
+                    let v = quoted.unwrap_or(line);
+                    block.end = span.end;
+                    match open.as_mut() {
+                        _ if quoted.is_some_and(|v| v.starts_with("--")) => {
+                            blocks.extend(std::mem::replace(open.as_mut(), State::Empty).finish());
+                            return Some(Block {
+                                kind: BlockKind::BlockQuote {
+                                    blocks: std::mem::take(blocks),
+                                    attribution: Some(inline(d, v[2..].trim_start())),
+                                },
+                                span: *block,
+                            });
+                        }
+                        _ if v.is_empty() => {
+                            blocks.extend(std::mem::replace(open.as_mut(), State::Empty).finish())
+                        }
+                        State::Paragraph(content, paragraph) => {
+                            content.push(soft_break(paragraph.end));
+                            content.extend(inline(d, v));
+                            paragraph.end = l.span(v).end;
+                        }
+                        _ => **open = State::Paragraph(inline(d, v), l.span(v)),
+                    }
                 }
 
-                State::Paragraph | State::List(_) => writeln!(f, "{}", Text::text(line))?,
-
-                State::Codeblock => {
-                    write!(
-                        f,
-                        "{}{}",
-                        E::end(FormatType::CodeBlockContents),
-                        E::end(FormatType::CodeBlock)
-                    )?;
-                    state = State::Empty;
+                State::Codeblock(_, _, _, block, _) => {
+                    block.end = span.end;
+                    return state.finish();
                 }
             }
+
+            // Definitions can be written without blank lines between them
+            let next = self.lines.clone().next();
+            if matches!(state, State::Footnote(..))
+                && next.is_some_and(|l| footnote_label(l.raw.trim()).is_some())
+            {
+                return state.finish();
+            }
+            // Metadata lines end paragraphs and lists, so includes are not
+            // rendered as text
+            if !matches!(state, State::Empty)
+                && state.open_codeblock().is_none()
+                && next.is_some_and(|l| l.raw.trim().starts_with("//!"))
+            {
+                return state.finish();
+            }
         }
 
-        Ok(())
+        if let Some((start, fence)) = state.open_codeblock() {
+            self.diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::UnclosedCodeBlock,
+                span: Span::of(start, fence),
+            });
+        }
+        state.finish()
+    }
+}
+
+impl<'a, E: FormatEngine> Display for Document<'a, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        push_html::<E>(f, self.parser())
     }
 }
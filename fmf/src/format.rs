@@ -6,33 +6,186 @@ pub enum ListType {
     /// Unordered list (-foo -bar -baz)
     Unordered,
 }
+/// Alignment of table column
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alignment {
+    /// Not specified (`---`)
+    None,
+    /// `:--`
+    Left,
+    /// `:-:`
+    Center,
+    /// `--:`
+    Right,
+}
+/// Kind of callout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalloutKind {
+    Note,
+    Tip,
+    Warning,
+    Danger,
+}
+
+impl CalloutKind {
+    /// Gets callout kind by its name (`note`, `tip`, `warning`, `danger`)
+    pub fn from_name(v: &str) -> Option<Self> {
+        [Self::Note, Self::Tip, Self::Warning, Self::Danger]
+            .into_iter()
+            .find(|kind| kind.name() == v)
+    }
+    /// Gets name of callout kind
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Note => "note",
+            Self::Tip => "tip",
+            Self::Warning => "warning",
+            Self::Danger => "danger",
+        }
+    }
+}
+/// Kind of diff code block line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffKind {
+    /// `+line`
+    Added,
+    /// `-line`
+    Removed,
+    /// ` line` or empty line
+    Context,
+    /// `@@ -1 +1 @@`, `+++ file` and other lines
+    Header,
+}
+/// Kind of highlighted code token (see [`Highlighter`](crate::highlight::Highlighter))
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    Keyword,
+    Type,
+    Function,
+    /// `true`, `null`, etc.
+    Constant,
+    Number,
+    String,
+    Comment,
+    /// `#[derive(Debug)]`, `@property`, `#include`
+    Attribute,
+    /// TOML and JSON keys
+    Key,
+    /// Shell variables
+    Variable,
+    /// Console prompt (`$`)
+    Prompt,
+}
+
+impl TokenKind {
+    /// Gets name of token kind
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Keyword => "keyword",
+            Self::Type => "type",
+            Self::Function => "function",
+            Self::Constant => "constant",
+            Self::Number => "number",
+            Self::String => "string",
+            Self::Comment => "comment",
+            Self::Attribute => "attribute",
+            Self::Key => "key",
+            Self::Variable => "variable",
+            Self::Prompt => "prompt",
+        }
+    }
+}
 /// Type of format that should be used.
 ///
 /// # Notes
 ///
 /// Some formats have unique syntax. Lets call `FOO` as start of
 /// FOO and `/FOO` as end of FOO:
-/// 1. `CodeBlock [CodeBlockMeta /CodeBlockMeta] CodeBlockContents [...] /CodeBlockContents /CodeBlock`
-/// 2. `List [ListItem [...] /ListItem]... /List`
-/// 3. `InlineLink InlineLinkUrl /InlineLinkUrl InlineLinkText [...] /InlineLinkText`
-/// 4. `CardAuthor CardAuthorAvatar /CardAuthorAvatar CardAuthorName /CardAuthorName CardAuthorDate
-///    /CardAuthorDate CardAuthorCategory /CardAuthorCategory`
+/// 1. `Heading [HeadingId /HeadingId] HeadingText [HeadingAnchor /HeadingAnchor] [...] /HeadingText /Heading`,
+///    id and anchor contain only heading id
+/// 2. `CodeBlock [CodeBlockLang /CodeBlockLang] CodeBlockBody CodeBlockMeta [CodeBlockTitle /CodeBlockTitle]
+///    [CodeBlockFile /CodeBlockFile] [CodeBlockLangName /CodeBlockLangName] /CodeBlockMeta CodeBlockContents
+///    [...] /CodeBlockContents /CodeBlockBody /CodeBlock`, language, title and file name contain only text.
+///    Contents of code blocks with known language contain `Token(_)` with only text. Numbered or highlighted
+///    lines and lines of diffs are wrapped: `CodeBlockLine [CodeBlockLineNumber /CodeBlockLineNumber] [...]
+///    /CodeBlockLine` (or `CodeBlockHighlightedLine`, `CodeBlockDiffLine(_)`, `CodeBlockHighlightedDiffLine(_)`),
+///    line number contains only text
+/// 3. `List [ListItem [...] [List ...] /ListItem]... /List`, nested lists are
+///    placed after the item text
+/// 4. `BlockQuote [Paragraph ...]... [BlockQuoteAttribution [...] /BlockQuoteAttribution] /BlockQuote`
+/// 5. `Table TableRow [TableHeaderCell [...] /TableHeaderCell]... /TableRow [TableRow [TableCell [...] /TableCell]... /TableRow]... /Table`
+/// 6. `Figure InlineImage ... /InlineImage [FigureCaption [...] /FigureCaption] /Figure`
+/// 7. `Callout(_) [CalloutTitle [...] /CalloutTitle] [...] /Callout(_)`, callout contains blocks
+/// 8. `Footnotes [Footnote [FootnoteId /FootnoteId] FootnoteText [...] [FootnoteBackLink /FootnoteBackLink] /FootnoteText /Footnote]... /Footnotes`,
+///    id and back link contain only text. Footnotes are placed at the end of document
+/// 9. `InlineLink [InlineLinkId /InlineLinkId] [InlineLinkUrl /InlineLinkUrl] InlineLinkText [...] /InlineLinkText`
+/// 10. `InlineImage InlineImageUrl /InlineImageUrl InlineImageAlt /InlineImageAlt /InlineImage`,
+///     url and alt contain only text
+/// 11. `InlineFootnote InlineLink ... /InlineFootnote`
+/// 12. `CardAuthor [CardAuthorAvatar /CardAuthorAvatar]... CardAuthorName /CardAuthorName CardAuthorDate
+///     /CardAuthorDate CardAuthorCategory /CardAuthorCategory`
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum FormatType {
     Heading(u8),
+    HeadingId,
+    HeadingText,
+    /// Link to heading itself
+    HeadingAnchor,
     Paragraph,
     List(ListType),
     ListItem(ListType),
+    BlockQuote,
+    BlockQuoteAttribution,
+    Table,
+    TableRow,
+    TableHeaderCell(Alignment),
+    TableCell(Alignment),
+    Figure,
+    FigureCaption,
+    Callout(CalloutKind),
+    CalloutTitle,
+    Footnotes,
+    Footnote,
+    FootnoteId,
+    FootnoteText,
+    /// Link to footnote reference
+    FootnoteBackLink,
     CodeBlock,
+    /// Language of code block as attribute
+    CodeBlockLang,
+    CodeBlockBody,
     CodeBlockMeta,
+    CodeBlockTitle,
+    CodeBlockFile,
+    /// Language of code block as text
+    CodeBlockLangName,
     CodeBlockContents,
+    CodeBlockLine,
+    CodeBlockHighlightedLine,
+    CodeBlockDiffLine(DiffKind),
+    /// Highlighted line of diff
+    CodeBlockHighlightedDiffLine(DiffKind),
+    CodeBlockLineNumber,
 
     InlineBold,
     InlineItalic,
     InlineCode,
+    InlineStrikethrough,
+    InlineUnderline,
+    InlineHighlight,
+    InlineSubscript,
+    InlineSuperscript,
+    InlineKeyboard,
+    /// Highlighted code token
+    Token(TokenKind),
     InlineLink,
+    InlineLinkId,
     InlineLinkUrl,
     InlineLinkText,
+    InlineImage,
+    InlineImageUrl,
+    InlineImageAlt,
+    InlineFootnote,
 
     CardAuthor,
     CardAuthorAvatar,
@@ -41,6 +194,33 @@ pub enum FormatType {
     CardAuthorCategory,
 }
 
+impl FormatType {
+    /// Checks if format is inline-formatting (`Inline*` formats)
+    pub fn is_inline(&self) -> bool {
+        matches!(
+            self,
+            Self::InlineBold
+                | Self::InlineItalic
+                | Self::InlineCode
+                | Self::InlineStrikethrough
+                | Self::InlineUnderline
+                | Self::InlineHighlight
+                | Self::InlineSubscript
+                | Self::InlineSuperscript
+                | Self::InlineKeyboard
+                | Self::Token(_)
+                | Self::InlineLink
+                | Self::InlineLinkId
+                | Self::InlineLinkUrl
+                | Self::InlineLinkText
+                | Self::InlineImage
+                | Self::InlineImageUrl
+                | Self::InlineImageAlt
+                | Self::InlineFootnote
+        )
+    }
+}
+
 /// Describes format engine
 pub trait FormatEngine {
     type CodeblockEngine: FormatEngine;
@@ -66,28 +246,101 @@ impl FormatEngine for ParagraphEngine {
     }
     fn begin(ty: FormatType) -> &'static str {
         match ty {
-            FormatType::Heading(1) => "<h1>",
-            FormatType::Heading(2) => "<h2>",
-            FormatType::Heading(3) => "<h3>",
-            FormatType::Heading(4) => "<h4>",
-            FormatType::Heading(5) => "<h5>",
-            FormatType::Heading(_) => "<h6>",
+            FormatType::Heading(1) => "<h1",
+            FormatType::Heading(2) => "<h2",
+            FormatType::Heading(3) => "<h3",
+            FormatType::Heading(4) => "<h4",
+            FormatType::Heading(5) => "<h5",
+            FormatType::Heading(_) => "<h6",
+            FormatType::HeadingId => " id=\"",
+            FormatType::HeadingText => ">",
+            FormatType::HeadingAnchor => "<a class=\"anchor\" href=\"#",
 
             FormatType::Paragraph => "<p>",
             FormatType::List(ListType::Ordered) => "<ol>",
             FormatType::List(ListType::Unordered) => "<ul>",
             FormatType::ListItem(_) => "<li>",
+            FormatType::BlockQuote => "<blockquote>",
+            FormatType::BlockQuoteAttribution => "<footer>",
+            FormatType::Table => "<div class=\"table\"><table>",
+            FormatType::TableRow => "<tr>",
+            FormatType::TableHeaderCell(Alignment::None) => "<th>",
+            FormatType::TableHeaderCell(Alignment::Left) => "<th style=\"text-align: left\">",
+            FormatType::TableHeaderCell(Alignment::Center) => "<th style=\"text-align: center\">",
+            FormatType::TableHeaderCell(Alignment::Right) => "<th style=\"text-align: right\">",
+            FormatType::TableCell(Alignment::None) => "<td>",
+            FormatType::TableCell(Alignment::Left) => "<td style=\"text-align: left\">",
+            FormatType::TableCell(Alignment::Center) => "<td style=\"text-align: center\">",
+            FormatType::TableCell(Alignment::Right) => "<td style=\"text-align: right\">",
+            FormatType::Figure => "<figure>",
+            FormatType::FigureCaption => "<figcaption>",
+            FormatType::Callout(CalloutKind::Note) => "<div class=\"callout note\">",
+            FormatType::Callout(CalloutKind::Tip) => "<div class=\"callout tip\">",
+            FormatType::Callout(CalloutKind::Warning) => "<div class=\"callout warning\">",
+            FormatType::Callout(CalloutKind::Danger) => "<div class=\"callout danger\">",
+            FormatType::CalloutTitle => "<div class=\"callout-title\">",
+            FormatType::Footnotes => "<section class=\"footnotes\"><ol>",
+            FormatType::Footnote => "<li",
+            FormatType::FootnoteId => " id=\"",
+            FormatType::FootnoteText => ">",
+            FormatType::FootnoteBackLink => " <a class=\"footnote-back\" href=\"#",
 
-            FormatType::CodeBlock => "<div class=\"codeblock\">",
-            FormatType::CodeBlockMeta => "<div class=\"prelude\"><span>",
+            FormatType::CodeBlock => "<div class=\"codeblock\"",
+            FormatType::CodeBlockLang => " data-lang=\"",
+            FormatType::CodeBlockBody => ">",
+            FormatType::CodeBlockMeta => "<div class=\"prelude\">",
+            FormatType::CodeBlockTitle => "<span class=\"title\">",
+            FormatType::CodeBlockFile => "<span class=\"file\">",
+            FormatType::CodeBlockLangName => "<span class=\"lang\">",
             FormatType::CodeBlockContents => "<pre>",
+            FormatType::CodeBlockLine => "<span class=\"line\">",
+            FormatType::CodeBlockHighlightedLine => "<span class=\"line highlighted\">",
+            FormatType::CodeBlockDiffLine(DiffKind::Added) => "<span class=\"line added\">",
+            FormatType::CodeBlockDiffLine(DiffKind::Removed) => "<span class=\"line removed\">",
+            FormatType::CodeBlockDiffLine(DiffKind::Context) => "<span class=\"line context\">",
+            FormatType::CodeBlockDiffLine(DiffKind::Header) => "<span class=\"line diff-header\">",
+            FormatType::CodeBlockHighlightedDiffLine(DiffKind::Added) => {
+                "<span class=\"line added highlighted\">"
+            }
+            FormatType::CodeBlockHighlightedDiffLine(DiffKind::Removed) => {
+                "<span class=\"line removed highlighted\">"
+            }
+            FormatType::CodeBlockHighlightedDiffLine(DiffKind::Context) => {
+                "<span class=\"line context highlighted\">"
+            }
+            FormatType::CodeBlockHighlightedDiffLine(DiffKind::Header) => {
+                "<span class=\"line diff-header highlighted\">"
+            }
+            FormatType::CodeBlockLineNumber => "<span class=\"line-number\">",
 
             FormatType::InlineBold => "<b>",
             FormatType::InlineItalic => "<i>",
             FormatType::InlineCode => "<code>",
+            FormatType::InlineStrikethrough => "<s>",
+            FormatType::InlineUnderline => "<u>",
+            FormatType::InlineHighlight => "<mark>",
+            FormatType::InlineSubscript => "<sub>",
+            FormatType::InlineSuperscript => "<sup>",
+            FormatType::InlineKeyboard => "<kbd>",
+            FormatType::Token(TokenKind::Keyword) => "<span class=\"tok-keyword\">",
+            FormatType::Token(TokenKind::Type) => "<span class=\"tok-type\">",
+            FormatType::Token(TokenKind::Function) => "<span class=\"tok-function\">",
+            FormatType::Token(TokenKind::Constant) => "<span class=\"tok-constant\">",
+            FormatType::Token(TokenKind::Number) => "<span class=\"tok-number\">",
+            FormatType::Token(TokenKind::String) => "<span class=\"tok-string\">",
+            FormatType::Token(TokenKind::Comment) => "<span class=\"tok-comment\">",
+            FormatType::Token(TokenKind::Attribute) => "<span class=\"tok-attribute\">",
+            FormatType::Token(TokenKind::Key) => "<span class=\"tok-key\">",
+            FormatType::Token(TokenKind::Variable) => "<span class=\"tok-variable\">",
+            FormatType::Token(TokenKind::Prompt) => "<span class=\"tok-prompt\">",
             FormatType::InlineLink => "<a",
+            FormatType::InlineLinkId => " id=\"",
             FormatType::InlineLinkUrl => " href=\"",
             FormatType::InlineLinkText => ">",
+            FormatType::InlineImage => "<img",
+            FormatType::InlineImageUrl => " src=\"",
+            FormatType::InlineImageAlt => " alt=\"",
+            FormatType::InlineFootnote => "<sup class=\"footnote\">",
 
             FormatType::CardAuthor => "<div class=\"author-card\">",
             FormatType::CardAuthorAvatar => "<img src=\"",
@@ -104,22 +357,62 @@ impl FormatEngine for ParagraphEngine {
             FormatType::Heading(4) => "</h4>",
             FormatType::Heading(5) => "</h5>",
             FormatType::Heading(_) => "</h6>",
+            FormatType::HeadingId => "\"",
+            FormatType::HeadingText => "",
+            FormatType::HeadingAnchor => "\">#</a>",
 
             FormatType::Paragraph => "</p>",
             FormatType::List(ListType::Ordered) => "</ol>",
             FormatType::List(ListType::Unordered) => "</ul>",
             FormatType::ListItem(_) => "</li>",
+            FormatType::BlockQuote => "</blockquote>",
+            FormatType::BlockQuoteAttribution => "</footer>",
+            FormatType::Table => "</table></div>",
+            FormatType::TableRow => "</tr>",
+            FormatType::TableHeaderCell(_) => "</th>",
+            FormatType::TableCell(_) => "</td>",
+            FormatType::Figure => "</figure>",
+            FormatType::FigureCaption => "</figcaption>",
+            FormatType::Callout(_) => "</div>",
+            FormatType::CalloutTitle => "</div>",
+            FormatType::Footnotes => "</ol></section>",
+            FormatType::Footnote => "</li>",
+            FormatType::FootnoteId => "\"",
+            FormatType::FootnoteText => "",
+            FormatType::FootnoteBackLink => "\">↩</a>",
 
             FormatType::CodeBlock => "</div>",
-            FormatType::CodeBlockMeta => "</span></div>",
+            FormatType::CodeBlockLang => "\"",
+            FormatType::CodeBlockBody => "",
+            FormatType::CodeBlockMeta => "</div>",
+            FormatType::CodeBlockTitle => "</span>",
+            FormatType::CodeBlockFile => "</span>",
+            FormatType::CodeBlockLangName => "</span>",
             FormatType::CodeBlockContents => "</pre>",
+            FormatType::CodeBlockLine => "</span>",
+            FormatType::CodeBlockHighlightedLine => "</span>",
+            FormatType::CodeBlockDiffLine(_) => "</span>",
+            FormatType::CodeBlockHighlightedDiffLine(_) => "</span>",
+            FormatType::CodeBlockLineNumber => "</span>",
 
             FormatType::InlineBold => "</b>",
             FormatType::InlineItalic => "</i>",
             FormatType::InlineCode => "</code>",
+            FormatType::InlineStrikethrough => "</s>",
+            FormatType::InlineUnderline => "</u>",
+            FormatType::InlineHighlight => "</mark>",
+            FormatType::InlineSubscript => "</sub>",
+            FormatType::InlineSuperscript => "</sup>",
+            FormatType::InlineKeyboard => "</kbd>",
+            FormatType::Token(_) => "</span>",
             FormatType::InlineLink => "</a>",
+            FormatType::InlineLinkId => "\"",
             FormatType::InlineLinkUrl => "\"",
             FormatType::InlineLinkText => "",
+            FormatType::InlineImage => ">",
+            FormatType::InlineImageUrl => "\"",
+            FormatType::InlineImageAlt => "\"",
+            FormatType::InlineFootnote => "</sup>",
 
             FormatType::CardAuthor => "</div>",
             FormatType::CardAuthorAvatar => "\" alt=\"\" width=\"32px\" height=\"32px\">",
@@ -147,4 +440,3 @@ impl FormatEngine for CodeblockEngine {
         ParagraphEngine::end(ty)
     }
 }
-
@@ -0,0 +1,419 @@
+use crate::format::TokenKind;
+
+/// Attribute syntax
+#[derive(Clone, Copy)]
+enum Attribute {
+    None,
+    /// Attribute starts with prefix and ends with matching `]` (`#[derive(Debug)]`)
+    Brackets(&'static str),
+    /// Attribute is prefix and name (`@property`, `#include`)
+    Word(&'static str),
+}
+
+/// Language syntax
+struct Syntax {
+    names: &'static [&'static str],
+    keywords: &'static [&'static str],
+    types: &'static [&'static str],
+    constants: &'static [&'static str],
+    line_comment: Option<&'static str>,
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    /// Tripled quotes start multi-line strings (`"""`)
+    triple_quotes: bool,
+    /// Strings can contain line breaks
+    multiline_strings: bool,
+    /// Names starting with uppercase letter are types
+    capitalized_types: bool,
+    attribute: Attribute,
+    /// Names and strings followed by `=` or `:` are keys, `[table]` lines are types
+    keys: bool,
+    /// `$name` and `${name}` are variables, comments start only after whitespace
+    variables: bool,
+    /// Lines starting with `$ ` or `# ` are commands, other lines are output
+    prompt: bool,
+}
+
+const NONE: Syntax = Syntax {
+    names: &[],
+    keywords: &[],
+    types: &[],
+    constants: &[],
+    line_comment: None,
+    block_comment: None,
+    quotes: &[],
+    triple_quotes: false,
+    multiline_strings: false,
+    capitalized_types: false,
+    attribute: Attribute::None,
+    keys: false,
+    variables: false,
+    prompt: false,
+};
+
+const SHELL_KEYWORDS: &[&str] = &[
+    "if", "then", "else", "elif", "fi", "for", "while", "until", "do", "done", "case", "esac",
+    "in", "function", "return", "export", "local", "select",
+];
+
+const SYNTAXES: &[Syntax] = &[
+    Syntax {
+        names: &["rust", "rs"],
+        keywords: &[
+            "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+            "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+            "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+            "type", "unsafe", "use", "where", "while",
+        ],
+        types: &[
+            "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32",
+            "i64", "i128", "isize", "f32", "f64",
+        ],
+        constants: &["true", "false"],
+        line_comment: Some("//"),
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"'],
+        multiline_strings: true,
+        capitalized_types: true,
+        attribute: Attribute::Brackets("#["),
+        ..NONE
+    },
+    Syntax {
+        names: &["c", "h"],
+        keywords: &[
+            "auto", "break", "case", "const", "continue", "default", "do", "else", "enum",
+            "extern", "for", "goto", "if", "inline", "register", "restrict", "return", "sizeof",
+            "static", "struct", "switch", "typedef", "union", "volatile", "while",
+        ],
+        types: &[
+            "void", "char", "short", "int", "long", "float", "double", "signed", "unsigned",
+            "bool", "size_t", "ssize_t", "int8_t", "int16_t", "int32_t", "int64_t", "uint8_t",
+            "uint16_t", "uint32_t", "uint64_t",
+        ],
+        constants: &["NULL", "true", "false"],
+        line_comment: Some("//"),
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        attribute: Attribute::Word("#"),
+        ..NONE
+    },
+    Syntax {
+        names: &["toml"],
+        constants: &["true", "false"],
+        line_comment: Some("#"),
+        quotes: &['"', '\''],
+        triple_quotes: true,
+        keys: true,
+        ..NONE
+    },
+    Syntax {
+        names: &["json"],
+        constants: &["true", "false", "null"],
+        quotes: &['"'],
+        keys: true,
+        ..NONE
+    },
+    Syntax {
+        names: &["sh", "shell", "bash", "zsh"],
+        keywords: SHELL_KEYWORDS,
+        line_comment: Some("#"),
+        quotes: &['"', '\''],
+        multiline_strings: true,
+        variables: true,
+        ..NONE
+    },
+    Syntax {
+        names: &["console", "terminal"],
+        keywords: SHELL_KEYWORDS,
+        line_comment: Some("#"),
+        quotes: &['"', '\''],
+        multiline_strings: true,
+        variables: true,
+        prompt: true,
+        ..NONE
+    },
+    Syntax {
+        names: &["python", "py"],
+        keywords: &[
+            "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+            "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in",
+            "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+            "with", "yield",
+        ],
+        types: &[
+            "int", "float", "str", "bool", "bytes", "list", "dict", "set", "tuple", "object",
+        ],
+        constants: &["True", "False", "None"],
+        line_comment: Some("#"),
+        quotes: &['"', '\''],
+        triple_quotes: true,
+        attribute: Attribute::Word("@"),
+        ..NONE
+    },
+];
+
+/// Token that is not finished at the end of line
+#[derive(Clone, Copy)]
+enum Open {
+    Comment,
+    /// Quote and if it is tripled
+    String(char, bool),
+}
+
+/// Splits code into tokens line by line. Supports Rust, C, TOML, JSON,
+/// shell, console and Python
+///
+/// ```
+/// use fmf::{format::TokenKind, highlight::Highlighter};
+///
+/// let mut highlighter = Highlighter::new("rust").unwrap();
+/// assert_eq!(
+///     highlighter.tokens("let x = 10; // ten"),
+///     [
+///         (Some(TokenKind::Keyword), "let"),
+///         (None, " x = "),
+///         (Some(TokenKind::Number), "10"),
+///         (None, "; "),
+///         (Some(TokenKind::Comment), "// ten"),
+///     ]
+/// );
+/// assert!(Highlighter::new("brainfuck").is_none());
+/// ```
+pub struct Highlighter {
+    syntax: &'static Syntax,
+    open: Option<Open>,
+    /// The last char of line before the current token
+    prev: Option<char>,
+    /// Only whitespace is before the current token
+    indent: bool,
+    /// Line is a command output
+    output: bool,
+}
+
+impl Highlighter {
+    /// Gets highlighter by language name, case-insensitive
+    pub fn new(lang: &str) -> Option<Self> {
+        let syntax = SYNTAXES.iter().find(|syntax| {
+            syntax
+                .names
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(lang))
+        })?;
+        Some(Self {
+            syntax,
+            open: None,
+            prev: None,
+            indent: true,
+            output: false,
+        })
+    }
+
+    /// Splits part of line into tokens. Text without kind is not highlighted
+    pub fn tokens<'a>(&mut self, v: &'a str) -> Vec<(Option<TokenKind>, &'a str)> {
+        let mut out: Vec<(Option<TokenKind>, &'a str)> = Vec::new();
+        let mut rest = v;
+        while !rest.is_empty() {
+            let (kind, len) = self.token(rest);
+            let (token, next) = rest.split_at(len);
+            match out.last_mut() {
+                // Text without highlighting is merged
+                Some((None, last)) if kind.is_none() => {
+                    let start = v.len() - last.len() - rest.len();
+                    *last = &v[start..v.len() - next.len()];
+                }
+                _ => out.push((kind, token)),
+            }
+            self.prev = token.chars().next_back().or(self.prev);
+            self.indent &= token.trim_start().is_empty();
+            rest = next;
+        }
+        out
+    }
+
+    /// Finishes line. Only multi-line strings and block comments are continued
+    pub fn end_line(&mut self) {
+        self.prev = None;
+        self.indent = true;
+        self.output = false;
+    }
+
+    /// Gets kind and length of the next token
+    fn token(&mut self, v: &str) -> (Option<TokenKind>, usize) {
+        let s = self.syntax;
+        match self.open {
+            _ if self.output => return (None, v.len()),
+            Some(Open::Comment) => {
+                let (_, end) = s.block_comment.expect("syntax has block comments");
+                let len = v.find(end).map(|idx| {
+                    self.open = None;
+                    idx + end.len()
+                });
+                return (Some(TokenKind::Comment), len.unwrap_or(v.len()));
+            }
+            Some(Open::String(quote, triple)) => {
+                let len = string_end(v, quote, triple).inspect(|_| self.open = None);
+                return (Some(TokenKind::String), len.unwrap_or(v.len()));
+            }
+            None => {}
+        }
+        let line_start = self.prev.is_none();
+        let blank = self.prev.is_none_or(char::is_whitespace);
+        let c = v.chars().next().expect("token is not empty");
+        let next = v[c.len_utf8()..].chars().next();
+
+        if s.prompt && line_start {
+            match v.starts_with("$ ") || v.starts_with("# ") {
+                true => return (Some(TokenKind::Prompt), 1),
+                false => {
+                    self.output = true;
+                    return (None, v.len());
+                }
+            }
+        }
+        if s.line_comment
+            .is_some_and(|comment| v.starts_with(comment) && (!s.variables || blank))
+        {
+            return (Some(TokenKind::Comment), v.len());
+        }
+        if let Some((start, end)) = s.block_comment.filter(|&(start, _)| v.starts_with(start)) {
+            let len = v[start.len()..]
+                .find(end)
+                .map(|idx| start.len() + idx + end.len());
+            if len.is_none() {
+                self.open = Some(Open::Comment);
+            }
+            return (Some(TokenKind::Comment), len.unwrap_or(v.len()));
+        }
+        // TOML tables
+        if s.keys && c == '[' && self.indent {
+            let len = v.find(']').map_or(v.len(), |idx| {
+                idx + v[idx..].len() - v[idx..].trim_start_matches(']').len()
+            });
+            return (Some(TokenKind::Type), len);
+        }
+        match s.attribute {
+            Attribute::Brackets(prefix) if v.starts_with(prefix) => {
+                let mut depth = 0;
+                let len = v.char_indices().find_map(|(idx, c)| {
+                    match c {
+                        '[' => depth += 1,
+                        ']' => depth -= 1,
+                        _ => {}
+                    }
+                    (depth == 0 && c == ']').then_some(idx + 1)
+                });
+                return (Some(TokenKind::Attribute), len.unwrap_or(v.len()));
+            }
+            Attribute::Word(prefix)
+                if v.starts_with(prefix)
+                    && v[prefix.len()..].starts_with(|c: char| c.is_alphabetic() || c == '_') =>
+            {
+                let name = &v[prefix.len()..];
+                let len = name
+                    .find(|c: char| !is_name(c) && c != '.')
+                    .unwrap_or(name.len());
+                return (Some(TokenKind::Attribute), prefix.len() + len);
+            }
+            _ => {}
+        }
+        if s.quotes.contains(&c) {
+            let triple = s.triple_quotes && is_triple(v, c);
+            let open = match triple {
+                true => 3,
+                false => 1,
+            };
+            let len = string_end(&v[open..], c, triple).map(|len| open + len);
+            if len.is_none() && (triple || s.multiline_strings) {
+                self.open = Some(Open::String(c, triple));
+            }
+            let len = len.unwrap_or(v.len());
+            return (Some(self.key_or(&v[len..], TokenKind::String)), len);
+        }
+        // Char literals (`'a'`, `'\n'`) in languages with `'` lifetimes
+        if c == '\'' && !s.quotes.contains(&c) {
+            let len = match next {
+                Some('\\') => v.get(3..).and_then(|v| v.find('\'')).map(|idx| idx + 4),
+                Some(ch) => v[1 + ch.len_utf8()..]
+                    .starts_with('\'')
+                    .then_some(2 + ch.len_utf8()),
+                None => None,
+            };
+            if let Some(len) = len {
+                return (Some(TokenKind::String), len);
+            }
+        }
+        if s.variables && c == '$' {
+            let len = match next {
+                Some('{') => v.find('}').map_or(v.len(), |idx| idx + 1),
+                Some(c) if is_name(c) => 1 + v[1..].find(|c| !is_name(c)).unwrap_or(v.len() - 1),
+                Some('?' | '#' | '@' | '*' | '!' | '$' | '-') => 2,
+                _ => return (None, 1),
+            };
+            return (Some(TokenKind::Variable), len);
+        }
+        if c.is_ascii_digit() {
+            let mut len = v.len();
+            let mut chars = v.char_indices().peekable();
+            while let Some((idx, c)) = chars.next() {
+                let fraction = c == '.' && chars.peek().is_some_and(|(_, c)| c.is_ascii_digit());
+                if !is_name(c) && !fraction {
+                    len = idx;
+                    break;
+                }
+            }
+            return (Some(TokenKind::Number), len);
+        }
+        if c.is_alphabetic() || c == '_' {
+            // TOML keys can be dotted and contain `-`
+            let len = v
+                .find(|c: char| !(is_name(c) || s.keys && matches!(c, '-' | '.')))
+                .unwrap_or(v.len());
+            let (name, rest) = v.split_at(len);
+            let kind = match name {
+                _ if s.keywords.contains(&name) => Some(TokenKind::Keyword),
+                _ if s.types.contains(&name) => Some(TokenKind::Type),
+                _ if s.constants.contains(&name) => Some(TokenKind::Constant),
+                _ if s.capitalized_types && c.is_uppercase() => Some(TokenKind::Type),
+                _ if rest.starts_with('(') => Some(TokenKind::Function),
+                _ if self.key_or(rest, TokenKind::String) == TokenKind::Key => Some(TokenKind::Key),
+                _ => None,
+            };
+            return (kind, len);
+        }
+        (None, c.len_utf8())
+    }
+
+    /// Gets [`TokenKind::Key`] if token followed by `rest` is a key, otherwise `kind`
+    fn key_or(&self, rest: &str, kind: TokenKind) -> TokenKind {
+        match self.syntax.keys && rest.trim_start().starts_with(['=', ':']) {
+            true => TokenKind::Key,
+            false => kind,
+        }
+    }
+}
+
+/// Checks if `v` starts with three quotes
+fn is_triple(v: &str, quote: char) -> bool {
+    v.chars().take(3).filter(|&c| c == quote).count() == 3
+}
+
+fn is_name(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Gets length of string contents with closing quote. Escaped quotes are skipped
+fn string_end(v: &str, quote: char, triple: bool) -> Option<usize> {
+    let mut chars = v.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            _ if c != quote => {}
+            _ if !triple => return Some(idx + 1),
+            _ if is_triple(&v[idx..], quote) => return Some(idx + 3),
+            _ => {}
+        }
+    }
+    None
+}
@@ -0,0 +1,182 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    ast::{Block, BlockKind, Position, Span},
+    diagnostic::{Diagnostic, DiagnosticKind},
+    document::{metadata_entry, Document},
+    format::DefaultFormatEngine,
+};
+
+/// Maximum count of nested includes
+pub const MAX_DEPTH: usize = 16;
+
+/// Loads included documents. Implemented for [`FsResolver`] and for
+/// `HashMap<String, String>` of names and sources (in-memory documents)
+pub trait Resolver {
+    /// Gets name of document `path` included from document `from`. By
+    /// default path is relative to the directory of `from`, `.` and `..` are
+    /// removed, so the same document always has the same name
+    fn resolve(&self, from: &str, path: &str) -> String {
+        let dir = Path::new(from).parent().unwrap_or(Path::new(""));
+        let mut name = PathBuf::new();
+        for component in dir.join(path).components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir
+                    if matches!(name.components().next_back(), Some(Component::Normal(_))) =>
+                {
+                    name.pop();
+                }
+                component => name.push(component),
+            }
+        }
+        name.to_string_lossy().into_owned()
+    }
+    /// Loads source of document by its name
+    fn load(&mut self, name: &str) -> Result<String, String>;
+}
+
+/// Loads documents from file system
+pub struct FsResolver;
+impl Resolver for FsResolver {
+    fn load(&mut self, name: &str) -> Result<String, String> {
+        fs::read_to_string(name).map_err(|e| e.to_string())
+    }
+}
+
+impl Resolver for HashMap<String, String> {
+    fn load(&mut self, name: &str) -> Result<String, String> {
+        self.get(name)
+            .cloned()
+            .ok_or_else(|| "not found".to_owned())
+    }
+}
+
+/// Document with expanded includes
+pub struct Expanded {
+    /// Source with included documents in place of `//! include: path` lines
+    pub v: String,
+    /// Failed includes. Contains document name and diagnostic in it
+    pub diagnostics: Vec<(String, Diagnostic)>,
+    /// Parts of source copied from documents: start in `v`, document name
+    /// and start in document
+    parts: Vec<(usize, String, Position)>,
+}
+
+impl Expanded {
+    /// Gets document name and position in it of position in expanded source
+    pub fn locate(&self, pos: Position) -> (&str, Position) {
+        let idx = self
+            .parts
+            .partition_point(|&(start, ..)| start <= pos.offset);
+        let (start, name, origin) = &self.parts[idx.saturating_sub(1)];
+        let end = pos.offset.clamp(*start, self.v.len());
+        (name, origin.advance(&self.v[*start..end]))
+    }
+
+    /// Gets document name and diagnostic in it of diagnostic in expanded source
+    pub fn locate_diagnostic(&self, diagnostic: Diagnostic) -> (String, Diagnostic) {
+        let (name, start) = self.locate(diagnostic.span.start);
+        let (_, end) = self.locate(diagnostic.span.end);
+        let span = Span { start, end };
+        (name.to_owned(), Diagnostic { span, ..diagnostic })
+    }
+}
+
+/// Replaces `//! include: path` lines (also inside of callouts) by included documents.
+/// Includes are resolved by `resolver` relative to the including document,
+/// `name` is a name of the root document. Failed, cyclic and too deep
+/// (see [`MAX_DEPTH`]) includes are kept as is and reported
+///
+/// ```
+/// use std::collections::HashMap;
+/// use fmf::include::expand;
+///
+/// let mut files = HashMap::new();
+/// files.insert("parts/footer.fmf".to_owned(), "Thanks for reading!".to_owned());
+/// let expanded = expand("post.fmf", "# Post\n//! include: parts/footer.fmf\n", &mut files);
+/// assert_eq!(expanded.v, "# Post\n\nThanks for reading!\n\n");
+///
+/// let expanded = expand("post.fmf", "//! include: post.fmf", &mut files);
+/// assert_eq!(expanded.diagnostics.len(), 1);
+/// ```
+pub fn expand(name: &str, v: &str, resolver: &mut impl Resolver) -> Expanded {
+    let mut out = Expanded {
+        v: String::new(),
+        diagnostics: Vec::new(),
+        parts: Vec::new(),
+    };
+    expand_into(&mut out, name, v, &mut vec![name.to_owned()], resolver);
+    out
+}
+
+/// Expands document `v` to the end of `out`. `stack` contains names of
+/// documents that include it
+fn expand_into(
+    out: &mut Expanded,
+    name: &str,
+    v: &str,
+    stack: &mut Vec<String>,
+    resolver: &mut impl Resolver,
+) {
+    out.parts
+        .push((out.v.len(), name.to_owned(), Position::default()));
+    let mut last = 0;
+    let mut paths = Vec::new();
+    for block in Document::<DefaultFormatEngine>::new(v).blocks() {
+        includes(block, &mut paths);
+    }
+    for (path, span) in paths {
+        let included = resolver.resolve(name, path);
+        let loaded = if stack.contains(&included) {
+            Err(DiagnosticKind::IncludeCycle)
+        } else if stack.len() > MAX_DEPTH {
+            Err(DiagnosticKind::IncludeTooDeep)
+        } else {
+            resolver
+                .load(&included)
+                .map_err(DiagnosticKind::IncludeFailed)
+        };
+        let source = match loaded {
+            Ok(v) => v,
+            Err(kind) => {
+                out.diagnostics
+                    .push((name.to_owned(), Diagnostic { kind, span }));
+                continue;
+            }
+        };
+
+        out.v.push_str(&v[last..span.start.offset]);
+        // Blank line before included document, so the previous block is finished
+        if !out.v.is_empty() && !out.v.ends_with("\n\n") {
+            out.v.push('\n');
+        }
+        stack.push(included.clone());
+        expand_into(out, &included, &source, stack, resolver);
+        stack.pop();
+        // Blank line after included document, so its last block is finished
+        if !out.v.ends_with('\n') {
+            out.v.push('\n');
+        }
+        last = span.end.offset;
+        out.parts.push((out.v.len(), name.to_owned(), span.end));
+    }
+    out.v.push_str(&v[last..]);
+}
+
+/// Collects paths and spans of include lines of block and callouts inside of it
+fn includes<'a>(block: Block<'a>, out: &mut Vec<(&'a str, Span)>) {
+    match block.kind {
+        BlockKind::Meta(v) => {
+            if let Some(("include", path)) = metadata_entry(v) {
+                out.push((path, block.span));
+            }
+        }
+        BlockKind::Callout { blocks, .. } => blocks.into_iter().for_each(|b| includes(b, out)),
+        _ => {}
+    }
+}
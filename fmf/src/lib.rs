@@ -15,6 +15,17 @@
 //! println!("{document}");
 //! ```
 //!
+//! Document can be parsed into syntax tree (see [`ast`]) without formatting.
+//! Every node has a span in source:
+//! ```
+//! use fmf::{ast::BlockKind, document::Document, format::DefaultFormatEngine};
+//!
+//! let blocks = Document::<DefaultFormatEngine>::new("\n  # Hello, world!").parse();
+//! assert!(matches!(blocks[0].kind, BlockKind::Heading { level: 1, .. }));
+//! assert_eq!(blocks[0].span.range(), 3..18);
+//! assert_eq!((blocks[0].span.start.line, blocks[0].span.start.column), (2, 3));
+//! ```
+//!
 //! ## Documentation
 //!
 //! There only 0 contexts, 0 inline formatting and 0 special formats.
@@ -60,16 +71,15 @@
 //!
 //! #### Code blocks
 //!
-//! Like markdown, code blocks defined using three `` ` `` symbols:
+//! Like markdown, code blocks defined using three or more `` ` `` or `~` symbols.
+//! Code block is closed by a line of the same symbols that is not shorter:
 //!
-//! ```markdown
-//! (due markdown limits, code blocks here starts with space, but it not required)
-//!  ```language: foo, filename: bar, ...
+//! ````markdown
+//! ```language: foo, filename: bar, ...
 //! int a = 10;
 //! print(\*a\*); // To use inline-formatting in code blocks add `\` before format symbol
-//!  ```_
-//! Please ignore `_` in line upper
 //! ```
+//! ````
 //!
 //! #### Ordered and unordered lists
 //!
@@ -97,8 +107,21 @@
 //! 4. Links. Example: `\(google.com)`, `\(google.com)[Google]`, `\[Just blue text]`
 //!
 
+/// Syntax tree
+pub mod ast;
+/// Document diagnostics
+pub mod diagnostic;
 /// Document implementation
 pub mod document;
 /// Format defination
 pub mod format;
-
+/// Syntax highlighting
+pub mod highlight;
+/// Include directives
+pub mod include;
+/// Pull parser
+pub mod parser;
+/// Syntax tree serialization into FMF
+pub mod serialize;
+/// Heading slugs
+pub mod slug;
@@ -0,0 +1,859 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+    fmt::{Display, Write},
+    marker::PhantomData,
+};
+
+use crate::{
+    ast::{AuthorCard, Block, BlockKind, Inline, InlineKind, OutlineItem, Span, TableRow},
+    document::{Blocks, Document},
+    format::{Alignment, DefaultFormatEngine, DiffKind, FormatEngine, FormatType, ListType},
+    highlight::Highlighter,
+};
+
+/// Parser event
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event<'a> {
+    /// Begin of format
+    Start(FormatType),
+    /// End of format
+    End(FormatType),
+    /// Plain text, not escaped. Generated text (like heading ids) is owned
+    Text(Cow<'a, str>),
+    /// End of source line in paragraphs, list items and code blocks
+    SoftBreak,
+    /// Author card (`/// name / date / category`)
+    AuthorCard(AuthorCard<'a>),
+    /// Metadata line (`//! key: value`)
+    Meta(&'a str),
+}
+
+/// Pull parser. Iterates over document [`Event`]s.
+///
+/// Events are yielded in the same order as [`FormatType`] begin/end pairs
+/// (see [`FormatType`] docs). Source is parsed one block at a time, so events
+/// can be filtered or transformed before formatting:
+///
+/// ```
+/// use fmf::{format::DefaultFormatEngine, parser::{push_html, Event, Parser}};
+///
+/// let events = Parser::<DefaultFormatEngine>::new("/// fox / 1 Jan 2023 / misc\nHello!")
+///     .filter(|e| !matches!(e, Event::AuthorCard(_)));
+///
+/// let mut html = String::new();
+/// push_html::<DefaultFormatEngine>(&mut html, events).unwrap();
+/// assert_eq!(html, "<p>Hello!\n</p>");
+/// ```
+pub struct Parser<'a, E: FormatEngine = DefaultFormatEngine> {
+    blocks: Blocks<'a>,
+    queue: Queue<'a>,
+    /// Footnotes section is added
+    finished: bool,
+    _marker: PhantomData<E>,
+}
+
+impl<'a, E: FormatEngine> Parser<'a, E> {
+    pub fn new(v: &'a str) -> Self {
+        Self {
+            blocks: Document::<E>::new(v).blocks(),
+            queue: Queue::default(),
+            finished: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Adds link to heading itself at the start of every heading (off by default)
+    ///
+    /// ```
+    /// use fmf::{format::DefaultFormatEngine, parser::{push_html, Parser}};
+    ///
+    /// let events = Parser::<DefaultFormatEngine>::new("## Hi!").anchors(true);
+    /// let mut html = String::new();
+    /// push_html::<DefaultFormatEngine>(&mut html, events).unwrap();
+    /// assert_eq!(html, "<h2 id=\"hi\"><a class=\"anchor\" href=\"#hi\">#</a>Hi!</h2>");
+    /// ```
+    pub fn anchors(mut self, v: bool) -> Self {
+        self.queue.anchors = v;
+        self
+    }
+}
+
+impl<'a, E: FormatEngine> Iterator for Parser<'a, E> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.queue.events.is_empty() {
+            match self.blocks.next() {
+                Some(block) => push_block(&mut self.queue, block),
+                None if !self.finished => {
+                    self.finished = true;
+                    push_footnotes(&mut self.queue);
+                }
+                None => return None,
+            }
+        }
+        self.queue.events.pop_front()
+    }
+}
+
+/// Queue of events. Keeps state of the whole document
+#[derive(Default)]
+struct Queue<'a> {
+    events: VecDeque<Event<'a>>,
+    /// Add links to headings themselves
+    anchors: bool,
+    /// Footnote labels in order of the first reference
+    footnotes: Vec<&'a str>,
+    /// Footnote definitions. Only the first definition of label is kept
+    definitions: HashMap<&'a str, Vec<Inline<'a>>>,
+}
+
+fn push_block<'a>(queue: &mut Queue<'a>, block: Block<'a>) {
+    // Line breaks are added after every line, so they have empty spans
+    let soft_break = |end| Inline {
+        kind: InlineKind::SoftBreak,
+        span: Span { start: end, end },
+    };
+    match block.kind {
+        BlockKind::Heading { level, id, content } => {
+            queue
+                .events
+                .push_back(Event::Start(FormatType::Heading(level)));
+            queue.events.push_back(Event::Start(FormatType::HeadingId));
+            queue.events.push_back(Event::Text(Cow::Owned(id.clone())));
+            queue.events.push_back(Event::End(FormatType::HeadingId));
+            queue
+                .events
+                .push_back(Event::Start(FormatType::HeadingText));
+            if queue.anchors {
+                queue
+                    .events
+                    .push_back(Event::Start(FormatType::HeadingAnchor));
+                queue.events.push_back(Event::Text(Cow::Owned(id)));
+                queue
+                    .events
+                    .push_back(Event::End(FormatType::HeadingAnchor));
+            }
+            push_inlines(queue, content);
+            queue.events.push_back(Event::End(FormatType::HeadingText));
+            queue
+                .events
+                .push_back(Event::End(FormatType::Heading(level)));
+        }
+        BlockKind::Paragraph(mut content) => {
+            content.push(soft_break(block.span.end));
+            push_wrapped(queue, FormatType::Paragraph, content)
+        }
+        BlockKind::List { ty, items } => {
+            queue.events.push_back(Event::Start(FormatType::List(ty)));
+            for mut item in items {
+                item.content.push(soft_break(item.span.end));
+                queue
+                    .events
+                    .push_back(Event::Start(FormatType::ListItem(ty)));
+                push_inlines(queue, item.content);
+                for block in item.blocks {
+                    push_block(queue, block);
+                }
+                queue.events.push_back(Event::End(FormatType::ListItem(ty)));
+            }
+            queue.events.push_back(Event::End(FormatType::List(ty)));
+        }
+        BlockKind::BlockQuote {
+            blocks,
+            attribution,
+        } => {
+            queue.events.push_back(Event::Start(FormatType::BlockQuote));
+            for block in blocks {
+                push_block(queue, block);
+            }
+            if let Some(attribution) = attribution {
+                push_wrapped(queue, FormatType::BlockQuoteAttribution, attribution);
+            }
+            queue.events.push_back(Event::End(FormatType::BlockQuote));
+        }
+        BlockKind::Callout {
+            kind,
+            title,
+            blocks,
+        } => {
+            queue
+                .events
+                .push_back(Event::Start(FormatType::Callout(kind)));
+            if let Some(title) = title {
+                push_wrapped(queue, FormatType::CalloutTitle, title);
+            }
+            for block in blocks {
+                push_block(queue, block);
+            }
+            queue
+                .events
+                .push_back(Event::End(FormatType::Callout(kind)));
+        }
+        BlockKind::Table {
+            columns,
+            header,
+            rows,
+        } => {
+            queue.events.push_back(Event::Start(FormatType::Table));
+            push_row(queue, &columns, FormatType::TableHeaderCell, header);
+            for row in rows {
+                push_row(queue, &columns, FormatType::TableCell, row);
+            }
+            queue.events.push_back(Event::End(FormatType::Table));
+        }
+        BlockKind::Figure { url, alt, caption } => {
+            queue.events.push_back(Event::Start(FormatType::Figure));
+            push_image(queue, url, alt);
+            if !caption.is_empty() {
+                push_wrapped(queue, FormatType::FigureCaption, caption);
+            }
+            queue.events.push_back(Event::End(FormatType::Figure));
+        }
+        BlockKind::CodeBlock { meta, lines, .. } => {
+            queue.events.push_back(Event::Start(FormatType::CodeBlock));
+            if let Some(lang) = meta.lang {
+                push_text(queue, FormatType::CodeBlockLang, lang);
+            }
+            queue
+                .events
+                .push_back(Event::Start(FormatType::CodeBlockBody));
+            queue
+                .events
+                .push_back(Event::Start(FormatType::CodeBlockMeta));
+            for (ty, v) in [
+                (FormatType::CodeBlockTitle, meta.title),
+                (FormatType::CodeBlockFile, meta.file),
+                (FormatType::CodeBlockLangName, meta.lang),
+            ] {
+                if let Some(v) = v {
+                    push_text(queue, ty, v);
+                }
+            }
+            queue
+                .events
+                .push_back(Event::End(FormatType::CodeBlockMeta));
+            queue
+                .events
+                .push_back(Event::Start(FormatType::CodeBlockContents));
+            let mut highlighter = meta.lang.and_then(Highlighter::new);
+            // Lines are wrapped only if they are numbered, highlighted or diff rows
+            let numbers = meta.lines || meta.start.is_some();
+            let wrapped = numbers || meta.diff || !meta.highlight.is_empty();
+            let first = meta.start.unwrap_or(1);
+            for (number, mut line) in (first..).zip(lines) {
+                let diff = meta.diff.then(|| diff_kind(line.raw));
+                let ty = match (meta.highlight.iter().any(|r| r.contains(&number)), diff) {
+                    (true, Some(kind)) => FormatType::CodeBlockHighlightedDiffLine(kind),
+                    (true, None) => FormatType::CodeBlockHighlightedLine,
+                    (false, Some(kind)) => FormatType::CodeBlockDiffLine(kind),
+                    (false, None) => FormatType::CodeBlockLine,
+                };
+                if wrapped {
+                    queue.events.push_back(Event::Start(ty));
+                }
+                if numbers {
+                    queue
+                        .events
+                        .push_back(Event::Start(FormatType::CodeBlockLineNumber));
+                    queue
+                        .events
+                        .push_back(Event::Text(Cow::Owned(number.to_string())));
+                    queue
+                        .events
+                        .push_back(Event::End(FormatType::CodeBlockLineNumber));
+                }
+                // Diff marker is not highlighted, headers are not highlighted at all
+                if diff.is_some_and(|kind| kind != DiffKind::Header) {
+                    if let Some(InlineKind::Text(v)) = line.content.first_mut().map(|n| &mut n.kind)
+                    {
+                        let (marker, rest) = v.split_at(v.len().min(1));
+                        queue.events.push_back(Event::Text(Cow::Borrowed(marker)));
+                        *v = rest;
+                    }
+                }
+                match highlighter
+                    .as_mut()
+                    .filter(|_| diff != Some(DiffKind::Header))
+                {
+                    Some(highlighter) => {
+                        push_highlighted(queue, highlighter, line.content);
+                        highlighter.end_line();
+                    }
+                    None => push_inlines(queue, line.content),
+                }
+                if wrapped {
+                    queue.events.push_back(Event::End(ty));
+                }
+                queue.events.push_back(Event::SoftBreak);
+            }
+            queue
+                .events
+                .push_back(Event::End(FormatType::CodeBlockContents));
+            queue
+                .events
+                .push_back(Event::End(FormatType::CodeBlockBody));
+            queue.events.push_back(Event::End(FormatType::CodeBlock));
+        }
+        BlockKind::FootnoteDefinition { label, content } => {
+            queue.definitions.entry(label).or_insert(content);
+        }
+        BlockKind::AuthorCard(card) => queue.events.push_back(Event::AuthorCard(card)),
+        BlockKind::InvalidAuthorCard(_) => {}
+        BlockKind::Meta(v) => queue.events.push_back(Event::Meta(v)),
+    }
+}
+
+/// Gets kind of diff line by its first symbol
+fn diff_kind(raw: &str) -> DiffKind {
+    match raw.chars().next() {
+        _ if raw.starts_with("+++") || raw.starts_with("---") => DiffKind::Header,
+        Some('+') => DiffKind::Added,
+        Some('-') => DiffKind::Removed,
+        Some(' ') | None => DiffKind::Context,
+        Some(_) => DiffKind::Header,
+    }
+}
+
+fn push_row<'a>(
+    queue: &mut Queue<'a>,
+    columns: &[Alignment],
+    cell: fn(Alignment) -> FormatType,
+    row: TableRow<'a>,
+) {
+    queue.events.push_back(Event::Start(FormatType::TableRow));
+    for (&align, content) in columns.iter().zip(row.cells) {
+        push_wrapped(queue, cell(align), content);
+    }
+    queue.events.push_back(Event::End(FormatType::TableRow));
+}
+
+fn push_wrapped<'a>(queue: &mut Queue<'a>, ty: FormatType, nodes: Vec<Inline<'a>>) {
+    queue.events.push_back(Event::Start(ty));
+    push_inlines(queue, nodes);
+    queue.events.push_back(Event::End(ty));
+}
+
+fn push_text<'a>(queue: &mut Queue<'a>, ty: FormatType, v: &'a str) {
+    queue.events.push_back(Event::Start(ty));
+    queue.events.push_back(Event::Text(Cow::Borrowed(v)));
+    queue.events.push_back(Event::End(ty));
+}
+
+/// Pushes code line. Only text outside of formats is highlighted
+fn push_highlighted<'a>(
+    queue: &mut Queue<'a>,
+    highlighter: &mut Highlighter,
+    nodes: Vec<Inline<'a>>,
+) {
+    for node in nodes {
+        let InlineKind::Text(v) = node.kind else {
+            push_inlines(queue, vec![node]);
+            continue;
+        };
+        for (kind, v) in highlighter.tokens(v) {
+            match kind {
+                Some(kind) => push_text(queue, FormatType::Token(kind), v),
+                None => queue.events.push_back(Event::Text(Cow::Borrowed(v))),
+            }
+        }
+    }
+}
+
+fn push_inlines<'a>(queue: &mut Queue<'a>, nodes: Vec<Inline<'a>>) {
+    for node in nodes {
+        match node.kind {
+            InlineKind::Text(v) => queue.events.push_back(Event::Text(Cow::Borrowed(v))),
+            InlineKind::SoftBreak => queue.events.push_back(Event::SoftBreak),
+            InlineKind::Bold(v) => push_wrapped(queue, FormatType::InlineBold, v),
+            InlineKind::Italic(v) => push_wrapped(queue, FormatType::InlineItalic, v),
+            InlineKind::Code(v) => push_wrapped(queue, FormatType::InlineCode, v),
+            InlineKind::Strikethrough(v) => push_wrapped(queue, FormatType::InlineStrikethrough, v),
+            InlineKind::Underline(v) => push_wrapped(queue, FormatType::InlineUnderline, v),
+            InlineKind::Highlight(v) => push_wrapped(queue, FormatType::InlineHighlight, v),
+            InlineKind::Subscript(v) => push_wrapped(queue, FormatType::InlineSubscript, v),
+            InlineKind::Superscript(v) => push_wrapped(queue, FormatType::InlineSuperscript, v),
+            InlineKind::Keyboard(v) => {
+                queue
+                    .events
+                    .push_back(Event::Start(FormatType::InlineKeyboard));
+                queue.events.push_back(Event::Text(Cow::Borrowed(v)));
+                queue
+                    .events
+                    .push_back(Event::End(FormatType::InlineKeyboard));
+            }
+            InlineKind::Link { url, text } => {
+                queue.events.push_back(Event::Start(FormatType::InlineLink));
+                if let Some(url) = url {
+                    queue
+                        .events
+                        .push_back(Event::Start(FormatType::InlineLinkUrl));
+                    queue.events.push_back(Event::Text(Cow::Borrowed(url)));
+                    queue
+                        .events
+                        .push_back(Event::End(FormatType::InlineLinkUrl));
+                }
+                queue
+                    .events
+                    .push_back(Event::Start(FormatType::InlineLinkText));
+                match text {
+                    Some(text) => push_inlines(queue, text),
+                    None => queue
+                        .events
+                        .extend(url.map(|v| Event::Text(Cow::Borrowed(v)))),
+                }
+                queue
+                    .events
+                    .push_back(Event::End(FormatType::InlineLinkText));
+                queue.events.push_back(Event::End(FormatType::InlineLink));
+            }
+            InlineKind::Image { url, alt } => push_image(queue, url, alt),
+            InlineKind::FootnoteReference(label) => {
+                // Footnotes are numbered in order of the first reference
+                let (n, first) = match queue.footnotes.iter().position(|&l| l == label) {
+                    Some(idx) => (idx + 1, false),
+                    None => {
+                        queue.footnotes.push(label);
+                        (queue.footnotes.len(), true)
+                    }
+                };
+                queue
+                    .events
+                    .push_back(Event::Start(FormatType::InlineFootnote));
+                queue.events.push_back(Event::Start(FormatType::InlineLink));
+                // The first reference is a target of back link
+                if first {
+                    queue
+                        .events
+                        .push_back(Event::Start(FormatType::InlineLinkId));
+                    queue
+                        .events
+                        .push_back(Event::Text(Cow::Owned(format!("fnref-{n}"))));
+                    queue.events.push_back(Event::End(FormatType::InlineLinkId));
+                }
+                queue
+                    .events
+                    .push_back(Event::Start(FormatType::InlineLinkUrl));
+                queue
+                    .events
+                    .push_back(Event::Text(Cow::Owned(format!("#fn-{n}"))));
+                queue
+                    .events
+                    .push_back(Event::End(FormatType::InlineLinkUrl));
+                queue
+                    .events
+                    .push_back(Event::Start(FormatType::InlineLinkText));
+                queue
+                    .events
+                    .push_back(Event::Text(Cow::Owned(n.to_string())));
+                queue
+                    .events
+                    .push_back(Event::End(FormatType::InlineLinkText));
+                queue
+                    .events
+                    .push_back(Event::End(FormatType::InlineFootnote));
+            }
+        }
+    }
+}
+
+/// Pushes section with referenced footnotes. Undefined footnotes are empty
+fn push_footnotes(queue: &mut Queue<'_>) {
+    if queue.footnotes.is_empty() {
+        return;
+    }
+    queue.events.push_back(Event::Start(FormatType::Footnotes));
+    // Footnotes can reference other footnotes, so the list grows
+    let mut idx = 0;
+    while let Some(&label) = queue.footnotes.get(idx) {
+        idx += 1;
+        queue.events.push_back(Event::Start(FormatType::Footnote));
+        queue.events.push_back(Event::Start(FormatType::FootnoteId));
+        queue
+            .events
+            .push_back(Event::Text(Cow::Owned(format!("fn-{idx}"))));
+        queue.events.push_back(Event::End(FormatType::FootnoteId));
+        queue
+            .events
+            .push_back(Event::Start(FormatType::FootnoteText));
+        let content = queue.definitions.remove(label).unwrap_or_default();
+        push_inlines(queue, content);
+        queue
+            .events
+            .push_back(Event::Start(FormatType::FootnoteBackLink));
+        queue
+            .events
+            .push_back(Event::Text(Cow::Owned(format!("fnref-{idx}"))));
+        queue
+            .events
+            .push_back(Event::End(FormatType::FootnoteBackLink));
+        queue.events.push_back(Event::End(FormatType::FootnoteText));
+        queue.events.push_back(Event::End(FormatType::Footnote));
+    }
+    queue.events.push_back(Event::End(FormatType::Footnotes));
+}
+
+fn push_image<'a>(queue: &mut Queue<'a>, url: &'a str, alt: Option<&'a str>) {
+    queue
+        .events
+        .push_back(Event::Start(FormatType::InlineImage));
+    queue
+        .events
+        .push_back(Event::Start(FormatType::InlineImageUrl));
+    queue.events.push_back(Event::Text(Cow::Borrowed(url)));
+    queue
+        .events
+        .push_back(Event::End(FormatType::InlineImageUrl));
+    // Image without alt gets an empty one
+    queue
+        .events
+        .push_back(Event::Start(FormatType::InlineImageAlt));
+    queue
+        .events
+        .extend(alt.map(|v| Event::Text(Cow::Borrowed(v))));
+    queue
+        .events
+        .push_back(Event::End(FormatType::InlineImageAlt));
+    queue.events.push_back(Event::End(FormatType::InlineImage));
+}
+
+/// Gets events of inline nodes
+pub(crate) fn inline_events(nodes: Vec<Inline<'_>>) -> VecDeque<Event<'_>> {
+    let mut queue = Queue::default();
+    push_inlines(&mut queue, nodes);
+    queue.events
+}
+
+struct ASCIIWriter<'a>(&'a str);
+impl<'a> Display for ASCIIWriter<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for fragment in self
+            .0
+            .split(|p| !matches!(p, 'a'..='z' | 'A'..='Z' | '0'..='9' | '_'))
+        {
+            f.write_str(fragment)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes text with escaped HTML symbols. Also escapes `"` if the second field is set
+struct HTMLWriter<'a>(&'a str, bool);
+impl<'a> Display for HTMLWriter<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '&' => f.write_str("&amp;")?,
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '"' if self.1 => f.write_str("&quot;")?,
+                _ => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn begin<E: FormatEngine>(stack: &[FormatType], ty: FormatType) -> &'static str {
+    match stack.contains(&FormatType::CodeBlockContents) {
+        true => <E::CodeblockEngine>::begin(ty),
+        false => E::begin(ty),
+    }
+}
+fn end<E: FormatEngine>(stack: &[FormatType], ty: FormatType) -> &'static str {
+    match stack.contains(&FormatType::CodeBlockContents) {
+        true => <E::CodeblockEngine>::end(ty),
+        false => E::end(ty),
+    }
+}
+
+/// Checks if format is a tag attribute, so it contains only text
+fn is_attribute(ty: &FormatType) -> bool {
+    matches!(
+        ty,
+        FormatType::HeadingId
+            | FormatType::HeadingAnchor
+            | FormatType::CodeBlockLang
+            | FormatType::FootnoteId
+            | FormatType::FootnoteBackLink
+            | FormatType::InlineLinkId
+            | FormatType::InlineLinkUrl
+            | FormatType::InlineImageUrl
+            | FormatType::InlineImageAlt
+    )
+}
+
+/// Gets format that finishes the tag and contains its text: link text for
+/// links, heading text for headings, footnote text for footnotes and body
+/// for code blocks
+fn text_part(ty: FormatType) -> Option<FormatType> {
+    match ty {
+        FormatType::Heading(_) => Some(FormatType::HeadingText),
+        FormatType::CodeBlock => Some(FormatType::CodeBlockBody),
+        FormatType::Footnote => Some(FormatType::FootnoteText),
+        FormatType::InlineLink => Some(FormatType::InlineLinkText),
+        _ => None,
+    }
+}
+
+/// Checks if format is a part of heading, code block or footnote
+fn is_block_part(ty: FormatType) -> bool {
+    matches!(
+        ty,
+        FormatType::HeadingId
+            | FormatType::HeadingText
+            | FormatType::HeadingAnchor
+            | FormatType::CodeBlockLang
+            | FormatType::CodeBlockBody
+            | FormatType::FootnoteId
+            | FormatType::FootnoteText
+            | FormatType::FootnoteBackLink
+    )
+}
+
+/// Closes formats until only `len` formats are open
+fn close_to<E: FormatEngine>(
+    w: &mut impl Write,
+    stack: &mut Vec<FormatType>,
+    len: usize,
+) -> std::fmt::Result {
+    let mut last = None;
+    while stack.len() > len {
+        let ty = stack.pop().expect("stack is longer than len");
+        // Tag is finished by its text, so tag without text gets an empty one
+        if let Some(text) = text_part(ty).filter(|&text| last != Some(text)) {
+            w.write_str(begin::<E>(stack, text))?;
+            w.write_str(end::<E>(stack, text))?;
+        }
+        w.write_str(end::<E>(stack, ty))?;
+        last = Some(ty);
+    }
+    Ok(())
+}
+
+/// Starts text if the innermost format is link or heading without text
+fn start_text<E: FormatEngine>(
+    w: &mut impl Write,
+    stack: &mut Vec<FormatType>,
+) -> std::fmt::Result {
+    if let Some(text) = stack.last().and_then(|&ty| text_part(ty)) {
+        w.write_str(begin::<E>(stack, text))?;
+        stack.push(text);
+    }
+    Ok(())
+}
+
+/// Closes inline formats and attributes after the innermost block, then
+/// starts its text if needed
+fn close_inline<E: FormatEngine>(
+    w: &mut impl Write,
+    stack: &mut Vec<FormatType>,
+) -> std::fmt::Result {
+    let len = stack
+        .iter()
+        .rposition(|ty| !ty.is_inline() && !is_attribute(ty))
+        .map_or(0, |idx| idx + 1);
+    close_to::<E>(w, stack, len)?;
+    start_text::<E>(w, stack)
+}
+
+/// Formats events using format engine `E`. Code block contents are formatted
+/// using `E::CodeblockEngine`.
+///
+/// Output is always balanced, even if events are not: ends without begin,
+/// heading, footnote, link and image parts outside of them, formats inside of
+/// ids, urls and alt texts and text inside of image (but outside of its parts) are
+/// skipped, inline formats are closed before the next block and all formats
+/// are closed at the end.
+pub fn push_html<'a, E: FormatEngine>(
+    w: &mut impl Write,
+    events: impl IntoIterator<Item = Event<'a>>,
+) -> std::fmt::Result {
+    let mut stack = Vec::new();
+
+    for event in events {
+        match event {
+            Event::Start(ty) if !ty.is_inline() && !is_block_part(ty) => {
+                close_inline::<E>(w, &mut stack)?;
+                w.write_str(begin::<E>(&stack, ty))?;
+                stack.push(ty);
+            }
+            Event::Start(ty) => {
+                let last = stack.last().copied();
+                match ty {
+                    _ if last.as_ref().is_some_and(is_attribute) => continue,
+                    FormatType::HeadingId | FormatType::HeadingText
+                        if !matches!(last, Some(FormatType::Heading(_))) =>
+                    {
+                        continue
+                    }
+                    FormatType::CodeBlockLang | FormatType::CodeBlockBody
+                        if last != Some(FormatType::CodeBlock) =>
+                    {
+                        continue
+                    }
+                    FormatType::FootnoteId | FormatType::FootnoteText
+                        if last != Some(FormatType::Footnote) =>
+                    {
+                        continue
+                    }
+                    FormatType::InlineLinkId
+                    | FormatType::InlineLinkUrl
+                    | FormatType::InlineLinkText
+                        if last != Some(FormatType::InlineLink) =>
+                    {
+                        continue
+                    }
+                    FormatType::InlineImageUrl | FormatType::InlineImageAlt
+                        if last != Some(FormatType::InlineImage) =>
+                    {
+                        continue
+                    }
+                    FormatType::HeadingId
+                    | FormatType::HeadingText
+                    | FormatType::CodeBlockLang
+                    | FormatType::CodeBlockBody
+                    | FormatType::FootnoteId
+                    | FormatType::FootnoteText
+                    | FormatType::InlineLinkId
+                    | FormatType::InlineLinkUrl
+                    | FormatType::InlineLinkText
+                    | FormatType::InlineImageUrl
+                    | FormatType::InlineImageAlt => {}
+                    // Image contains only url and alt
+                    _ if last == Some(FormatType::InlineImage) => continue,
+                    // Links are placed right inside of heading or footnote text
+                    FormatType::HeadingAnchor | FormatType::FootnoteBackLink => {
+                        start_text::<E>(w, &mut stack)?;
+                        let text = match ty {
+                            FormatType::HeadingAnchor => FormatType::HeadingText,
+                            _ => FormatType::FootnoteText,
+                        };
+                        if stack.last() != Some(&text) {
+                            continue;
+                        }
+                    }
+                    _ => start_text::<E>(w, &mut stack)?,
+                }
+                w.write_str(begin::<E>(&stack, ty))?;
+                stack.push(ty);
+            }
+            Event::End(ty) => {
+                let Some(mut idx) = stack.iter().rposition(|&t| t == ty) else {
+                    continue;
+                };
+                // Texts are the last parts of tags
+                if matches!(
+                    ty,
+                    FormatType::InlineLinkText
+                        | FormatType::HeadingText
+                        | FormatType::CodeBlockBody
+                        | FormatType::FootnoteText
+                ) && idx > 0
+                {
+                    idx -= 1;
+                }
+                close_to::<E>(w, &mut stack, idx)?;
+            }
+            Event::Text(_) | Event::SoftBreak if stack.last() == Some(&FormatType::InlineImage) => {
+            }
+            Event::Text(v) => {
+                start_text::<E>(w, &mut stack)?;
+                let attribute = stack.last().is_some_and(is_attribute);
+                write!(w, "{}", HTMLWriter(&v, attribute))?
+            }
+            Event::SoftBreak => {
+                start_text::<E>(w, &mut stack)?;
+                w.write_char('\n')?
+            }
+            Event::AuthorCard(card) => {
+                let AuthorCard { date, category, .. } = card;
+                close_inline::<E>(w, &mut stack)?;
+                w.write_str(E::begin(FormatType::CardAuthor))?;
+                // Every author gets an avatar, names are joined
+                for name in card.names() {
+                    write!(
+                        w,
+                        "{}/{}.jpg{}",
+                        E::begin(FormatType::CardAuthorAvatar),
+                        ASCIIWriter(name),
+                        E::end(FormatType::CardAuthorAvatar),
+                    )?;
+                }
+                w.write_str(E::begin(FormatType::CardAuthorName))?;
+                for (idx, name) in card.names().enumerate() {
+                    if idx != 0 {
+                        w.write_str(", ")?;
+                    }
+                    write!(w, "{}", HTMLWriter(name, false))?;
+                }
+                write!(
+                    w,
+                    "{}{}{}{}{}{}{}{}",
+                    E::end(FormatType::CardAuthorName),
+                    E::begin(FormatType::CardAuthorDate),
+                    HTMLWriter(date, false),
+                    E::end(FormatType::CardAuthorDate),
+                    E::begin(FormatType::CardAuthorCategory),
+                    HTMLWriter(category, false),
+                    E::end(FormatType::CardAuthorCategory),
+                    E::end(FormatType::CardAuthor)
+                )?
+            }
+            Event::Meta(_) => {}
+        }
+    }
+
+    close_to::<E>(w, &mut stack, 0)
+}
+
+/// Formats table of contents: nested unordered lists of links to headings.
+/// Headings deeper than the previous one are nested into its item
+///
+/// ```
+/// use fmf::{document::Document, format::DefaultFormatEngine, parser::push_toc};
+///
+/// let outline = Document::<DefaultFormatEngine>::new("## A\n### B\n## C").outline();
+/// let mut html = String::new();
+/// push_toc::<DefaultFormatEngine>(&mut html, &outline).unwrap();
+/// assert_eq!(
+///     html,
+///     "<ul><li><a href=\"#a\">A</a><ul><li><a href=\"#b\">B</a></li></ul></li>\
+///      <li><a href=\"#c\">C</a></li></ul>"
+/// );
+/// ```
+pub fn push_toc<E: FormatEngine>(w: &mut impl Write, outline: &[OutlineItem]) -> std::fmt::Result {
+    let (list, item) = (
+        FormatType::List(ListType::Unordered),
+        FormatType::ListItem(ListType::Unordered),
+    );
+    let mut events = Vec::new();
+    // Levels of open lists
+    let mut levels = Vec::new();
+    for heading in outline {
+        while levels.last().is_some_and(|&level| level > heading.level) {
+            events.extend([Event::End(item), Event::End(list)]);
+            levels.pop();
+        }
+        match levels.last() == Some(&heading.level) {
+            true => events.push(Event::End(item)),
+            false => {
+                events.push(Event::Start(list));
+                levels.push(heading.level);
+            }
+        }
+        events.extend([
+            Event::Start(item),
+            Event::Start(FormatType::InlineLink),
+            Event::Start(FormatType::InlineLinkUrl),
+            Event::Text(Cow::Owned(format!("#{}", heading.id))),
+            Event::End(FormatType::InlineLinkUrl),
+            Event::Start(FormatType::InlineLinkText),
+            Event::Text(Cow::Borrowed(&heading.title)),
+            Event::End(FormatType::InlineLinkText),
+        ]);
+    }
+    // Open lists are closed by `push_html`
+    push_html::<E>(w, events)
+}
@@ -0,0 +1,503 @@
+use std::fmt::{Display, Write};
+
+use crate::{
+    ast::{AuthorCard, Block, BlockKind, Inline, InlineKind, TableRow},
+    document::is_word,
+    format::{Alignment, ListType},
+};
+
+/// Formats syntax tree back into FMF in canonical layout. Parsing the output
+/// gives the same tree (except spans). Expects tree parsed with
+/// [`DefaultFormatEngine`](crate::format::DefaultFormatEngine).
+///
+/// Canonical layout is:
+/// 1. Blocks are separated by single blank line
+/// 2. Headings, list items and author cards have single space after marker
+/// 3. List item lines after the first one and blocks inside of items are
+///    indented by two spaces more than the item. Paragraphs and code blocks
+///    inside of items are preceded by blank line
+/// 4. Lines have no trailing whitespace (code block contents are kept as is)
+///
+/// ```
+/// use fmf::{document::Document, format::DefaultFormatEngine, serialize::Fmf};
+///
+/// let blocks = Document::<DefaultFormatEngine>::new("#Title\n///a/b/c\n-  item").parse();
+/// assert_eq!(Fmf(&blocks).to_string(), "# Title\n\n/// a / b / c\n\n- item\n");
+/// ```
+pub struct Fmf<'b, 'a>(pub &'b [Block<'a>]);
+
+impl<'b, 'a> Display for Fmf<'b, 'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (idx, block) in self.0.iter().enumerate() {
+            if idx != 0 {
+                f.write_char('\n')?;
+            }
+            write_block(f, block, "")?;
+            f.write_char('\n')?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes block. `indent` is written before every line of blocks inside of
+/// list items and quotes
+fn write_block(
+    f: &mut std::fmt::Formatter<'_>,
+    block: &Block<'_>,
+    indent: &str,
+) -> std::fmt::Result {
+    match &block.kind {
+        BlockKind::Heading { level, content, .. } => {
+            for _ in 0..*level {
+                f.write_char('#')?;
+            }
+            if !content.is_empty() {
+                f.write_char(' ')?;
+            }
+            let mut inlines = Inlines::new("", false);
+            inlines.write(f, content)?;
+            inlines.finish_line(f)
+        }
+        BlockKind::Paragraph(content) => {
+            // Lines of list items are checked for item markers
+            f.write_str(indent)?;
+            let mut inlines = Inlines::new(indent, !indent.is_empty());
+            inlines.escape_start = true;
+            inlines.line_empty = true;
+            inlines.write(f, content)?;
+            inlines.finish_line(f)
+        }
+        BlockKind::List { ty, items } => {
+            let marker = match ty {
+                ListType::Ordered => '@',
+                ListType::Unordered => '-',
+            };
+            let inner = format!("{indent}  ");
+            for (idx, item) in items.iter().enumerate() {
+                if idx != 0 {
+                    f.write_char('\n')?;
+                }
+                write!(f, "{indent}{marker}")?;
+                if item
+                    .content
+                    .first()
+                    .is_some_and(|node| node.kind != InlineKind::SoftBreak)
+                {
+                    f.write_char(' ')?;
+                }
+                let mut inlines = Inlines::new(&inner, true);
+                inlines.write(f, &item.content)?;
+                inlines.finish_line(f)?;
+                // Text after item text or nested list is continuation, so
+                // blocks are separated by blank line unless it is a nested list
+                let mut prev_list = false;
+                for block in &item.blocks {
+                    let list = matches!(block.kind, BlockKind::List { .. });
+                    f.write_str(if list && !prev_list { "\n" } else { "\n\n" })?;
+                    write_block(f, block, &inner)?;
+                    prev_list = list;
+                }
+            }
+            Ok(())
+        }
+        BlockKind::BlockQuote {
+            blocks,
+            attribution,
+        } => {
+            for (idx, block) in blocks.iter().enumerate() {
+                if idx != 0 {
+                    f.write_str("\n>\n")?;
+                }
+                write_block(f, block, "> ")?;
+            }
+            match attribution {
+                Some(content) => {
+                    if !blocks.is_empty() {
+                        f.write_char('\n')?;
+                    }
+                    f.write_str("> --")?;
+                    if !content.is_empty() {
+                        f.write_char(' ')?;
+                    }
+                    let mut inlines = Inlines::new("", false);
+                    inlines.write(f, content)?;
+                    inlines.finish_line(f)
+                }
+                None if blocks.is_empty() => f.write_char('>'),
+                None => Ok(()),
+            }
+        }
+        BlockKind::Figure { url, alt, caption } => {
+            write_image(f, url, *alt)?;
+            if !caption.is_empty() {
+                f.write_char('\n')?;
+                let mut inlines = Inlines::new("", false);
+                inlines.line_empty = true;
+                inlines.escape_meta = true;
+                inlines.write(f, caption)?;
+                inlines.finish_line(f)?;
+            }
+            Ok(())
+        }
+        BlockKind::Callout {
+            kind,
+            title,
+            blocks,
+        } => {
+            write!(f, ":::{}", kind.name())?;
+            if let Some(title) = title {
+                f.write_char(' ')?;
+                let mut inlines = Inlines::new("", false);
+                inlines.write(f, title)?;
+                inlines.finish_line(f)?;
+            }
+            for (idx, block) in blocks.iter().enumerate() {
+                f.write_str(if idx == 0 { "\n" } else { "\n\n" })?;
+                write_block(f, block, indent)?;
+            }
+            f.write_str("\n:::")
+        }
+        BlockKind::Table {
+            columns,
+            header,
+            rows,
+        } => {
+            write_row(f, header)?;
+            f.write_str("\n|")?;
+            for align in columns {
+                f.write_str(match align {
+                    Alignment::None => " --- |",
+                    Alignment::Left => " :-- |",
+                    Alignment::Center => " :-: |",
+                    Alignment::Right => " --: |",
+                })?;
+            }
+            for row in rows {
+                f.write_char('\n')?;
+                write_row(f, row)?;
+            }
+            Ok(())
+        }
+        BlockKind::CodeBlock { fence, meta, lines } => {
+            // Metadata is separated from fence if it starts with the same symbol
+            let sep = match meta.raw.starts_with(&fence[..1]) {
+                true => " ",
+                false => "",
+            };
+            write!(f, "{indent}{fence}{sep}{}", meta.raw)?;
+            for line in lines {
+                match line.raw.is_empty() {
+                    true => f.write_char('\n')?,
+                    false => write!(f, "\n{indent}{}", line.raw)?,
+                }
+            }
+            write!(f, "\n{indent}{fence}")
+        }
+        BlockKind::FootnoteDefinition { label, content } => {
+            write!(f, "^{label}:")?;
+            if content
+                .first()
+                .is_some_and(|node| node.kind != InlineKind::SoftBreak)
+            {
+                f.write_char(' ')?;
+            }
+            // Lines are checked for definition markers
+            let mut inlines = Inlines::new("", true);
+            inlines.write(f, content)?;
+            inlines.finish_line(f)
+        }
+        BlockKind::AuthorCard(AuthorCard {
+            name,
+            date,
+            category,
+        }) => write!(f, "/// {name} / {date} / {category}"),
+        BlockKind::InvalidAuthorCard("") => f.write_str("///"),
+        BlockKind::InvalidAuthorCard(v) => write!(f, "/// {v}"),
+        BlockKind::Meta("") => f.write_str("//!"),
+        BlockKind::Meta(v) => write!(f, "//! {v}"),
+    }
+}
+
+fn write_image(f: &mut std::fmt::Formatter<'_>, url: &str, alt: Option<&str>) -> std::fmt::Result {
+    write!(f, "\\!({url})")?;
+    match alt {
+        Some(alt) => write!(f, "[{alt}]"),
+        None => Ok(()),
+    }
+}
+
+fn write_row(f: &mut std::fmt::Formatter<'_>, row: &TableRow<'_>) -> std::fmt::Result {
+    f.write_char('|')?;
+    for cell in &row.cells {
+        f.write_char(' ')?;
+        if !cell.is_empty() {
+            let mut inlines = Inlines::new("", false);
+            inlines.in_table = true;
+            inlines.write(f, cell)?;
+            inlines.finish_line(f)?;
+            f.write_char(' ')?;
+        }
+        f.write_char('|')?;
+    }
+    Ok(())
+}
+
+/// Checks if symbol at the edge of text should be escaped
+type Edge = fn(char) -> bool;
+
+/// Gets text of leading text nodes
+fn text_prefix(nodes: &[Inline<'_>]) -> String {
+    nodes
+        .iter()
+        .map_while(|node| match node.kind {
+            InlineKind::Text(v) => Some(v),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Checks if line starting with `v` would be parsed as block other than paragraph
+fn is_block_marker(v: &str) -> bool {
+    // `^[` can't start footnote definition, and `\^[` is a reference
+    v.starts_with(['#', '-', '@', '>', '|'])
+        || (v.starts_with('^') && !v.starts_with("^["))
+        || v.starts_with("//!")
+        || v.starts_with("///")
+        || v.starts_with("~~~")
+}
+
+/// Inline nodes writer
+struct Inlines<'s> {
+    /// Indentation of lines after the first one
+    indent: &'s str,
+    /// Escape block markers at the start of lines after the first one
+    escape_breaks: bool,
+    /// Escape block markers if the next text starts a line
+    escape_start: bool,
+    /// Escape `//!` if the next text starts a line. Metadata lines end any
+    /// block, so it is escaped after every line break
+    escape_meta: bool,
+    /// Escape `]`
+    in_link: bool,
+    /// Escape `|`
+    in_table: bool,
+    /// Nodes are children of italic
+    in_italic: bool,
+    /// Nothing is written since the line start
+    line_empty: bool,
+    /// Nothing is written since the start of line contents
+    at_start: bool,
+    /// Last written symbol is whitespace
+    trailing_space: bool,
+}
+
+impl<'s> Inlines<'s> {
+    fn new(indent: &'s str, escape_breaks: bool) -> Self {
+        Self {
+            indent,
+            escape_breaks,
+            escape_start: false,
+            escape_meta: false,
+            in_link: false,
+            in_table: false,
+            in_italic: false,
+            line_empty: false,
+            at_start: true,
+            trailing_space: false,
+        }
+    }
+
+    /// Writes lone `\\` if line is empty, so it is not a blank line, or if
+    /// line ends with whitespace, so it is not trimmed
+    fn finish_line(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line_empty || self.trailing_space {
+            true => f.write_char('\\'),
+            false => Ok(()),
+        }
+    }
+
+    fn write(&mut self, f: &mut std::fmt::Formatter<'_>, nodes: &[Inline<'_>]) -> std::fmt::Result {
+        for (idx, node) in nodes.iter().enumerate() {
+            if node.kind == InlineKind::SoftBreak {
+                self.finish_line(f)?;
+                write!(f, "\n{}", self.indent)?;
+                self.escape_start = self.escape_breaks;
+                self.escape_meta = true;
+                self.line_empty = true;
+                self.at_start = true;
+                self.trailing_space = false;
+                continue;
+            }
+            let escape_start = std::mem::take(&mut self.escape_start);
+            let escape_meta = std::mem::take(&mut self.escape_meta);
+            let at_start = std::mem::take(&mut self.at_start);
+
+            self.line_empty = false;
+            let (marker, children) = match &node.kind {
+                InlineKind::Text(v) => {
+                    // `_` is italic only at word boundaries, so symbols around
+                    // it are escaped to keep italic
+                    let italic = |node: Option<&Inline<'_>>| {
+                        matches!(
+                            node,
+                            Some(Inline {
+                                kind: InlineKind::Italic(_),
+                                ..
+                            })
+                        )
+                    };
+                    let edge = |inside: bool, outside: bool| -> Edge {
+                        match (inside, outside) {
+                            (true, true) => |c| c.is_whitespace() || is_word(c),
+                            (true, false) => char::is_whitespace,
+                            (false, true) => is_word,
+                            (false, false) => |_| false,
+                        }
+                    };
+                    let first = edge(
+                        self.in_italic && idx == 0,
+                        italic(idx.checked_sub(1).and_then(|idx| nodes.get(idx))),
+                    );
+                    let last = edge(
+                        self.in_italic && idx + 1 == nodes.len(),
+                        italic(nodes.get(idx + 1)),
+                    );
+                    // Block marker can be split between several text nodes
+                    let marker = (escape_start || escape_meta) && {
+                        let prefix = text_prefix(&nodes[idx..]);
+                        match escape_start {
+                            true => is_block_marker(&prefix),
+                            false => prefix.starts_with("//!"),
+                        }
+                    };
+                    self.write_text(f, v, marker, at_start, (first, last))?;
+                    continue;
+                }
+                InlineKind::SoftBreak => unreachable!("soft break is written above"),
+                InlineKind::Link { url, text } => {
+                    if let Some(url) = url {
+                        write!(f, "\\({url})")?;
+                    }
+                    // `[` right after `\(url)` starts link text, so such link
+                    // is written with text (it is formatted the same)
+                    let bracket = matches!(
+                        nodes.get(idx + 1),
+                        Some(Inline { kind: InlineKind::Text(v), .. }) if v.starts_with('[')
+                    );
+                    match (text, url) {
+                        (Some(text), _) => {
+                            f.write_str(if url.is_some() { "[" } else { "\\[" })?;
+                            let in_link = std::mem::replace(&mut self.in_link, true);
+                            let in_italic = std::mem::take(&mut self.in_italic);
+                            self.write(f, text)?;
+                            self.in_link = in_link;
+                            self.in_italic = in_italic;
+                            f.write_char(']')?;
+                        }
+                        (None, Some(url)) if bracket => {
+                            f.write_char('[')?;
+                            let in_link = std::mem::replace(&mut self.in_link, true);
+                            self.write_text(f, url, false, false, (|_| false, |_| false))?;
+                            self.in_link = in_link;
+                            f.write_char(']')?;
+                        }
+                        (None, _) => {}
+                    }
+                    self.trailing_space = false;
+                    continue;
+                }
+                InlineKind::Image { url, alt } => {
+                    write_image(f, url, *alt)?;
+                    self.trailing_space = false;
+                    continue;
+                }
+                InlineKind::FootnoteReference(label) => {
+                    write!(f, "\\^[{label}]")?;
+                    self.trailing_space = false;
+                    continue;
+                }
+                InlineKind::Keyboard(key) => {
+                    write!(f, "\\<{key}>")?;
+                    self.trailing_space = false;
+                    continue;
+                }
+                InlineKind::Strikethrough(v)
+                | InlineKind::Underline(v)
+                | InlineKind::Highlight(v)
+                | InlineKind::Subscript(v)
+                | InlineKind::Superscript(v) => {
+                    let marker = match node.kind {
+                        InlineKind::Strikethrough(_) => '~',
+                        InlineKind::Underline(_) => '+',
+                        InlineKind::Highlight(_) => '=',
+                        InlineKind::Subscript(_) => ',',
+                        _ => '\'',
+                    };
+                    write!(f, "\\{marker}[")?;
+                    let in_link = std::mem::replace(&mut self.in_link, true);
+                    let in_italic = std::mem::take(&mut self.in_italic);
+                    self.write(f, v)?;
+                    self.in_link = in_link;
+                    self.in_italic = in_italic;
+                    f.write_char(']')?;
+                    self.trailing_space = false;
+                    continue;
+                }
+                InlineKind::Bold(v) => ('*', v),
+                InlineKind::Italic(v) => ('_', v),
+                InlineKind::Code(v) => ('`', v),
+            };
+            f.write_char(marker)?;
+            let in_italic = std::mem::replace(&mut self.in_italic, marker == '_');
+            self.write(f, children)?;
+            self.in_italic = in_italic;
+            f.write_char(marker)?;
+            self.trailing_space = false;
+        }
+        Ok(())
+    }
+
+    /// Writes text. `block_marker` is set if text starts a line with block
+    /// marker. `edges` are checks of the first and the last symbols that
+    /// should be escaped
+    fn write_text(
+        &mut self,
+        f: &mut std::fmt::Formatter<'_>,
+        v: &str,
+        block_marker: bool,
+        at_start: bool,
+        edges: (Edge, Edge),
+    ) -> std::fmt::Result {
+        if !v.is_empty() {
+            self.trailing_space = v.ends_with(char::is_whitespace);
+        }
+        // Leading whitespace is trimmed and `:::` line closes callout, so they are escaped
+        let escaped = |idx: usize, c: char| {
+            let first = idx == 0
+                && (block_marker
+                    || (at_start && c.is_whitespace())
+                    || (at_start && c == ':')
+                    || edges.0(c));
+            let last = idx + c.len_utf8() == v.len() && edges.1(c);
+            first
+                || last
+                || matches!(c, '*' | '`' | '\\')
+                || (c == ']' && self.in_link)
+                || (c == '|' && self.in_table)
+        };
+        // `_` between letters or digits is text, so `snake_case` is kept as is
+        let letter = |c: Option<(usize, char)>| {
+            c.is_some_and(|(idx, c)| c.is_alphanumeric() && !escaped(idx, c))
+        };
+        for (idx, c) in v.char_indices() {
+            let underscore = c == '_'
+                && !(letter(v[..idx].char_indices().next_back())
+                    && letter(v[idx + 1..].chars().next().map(|c| (idx + 1, c))));
+            if escaped(idx, c) || underscore {
+                f.write_char('\\')?;
+            }
+            f.write_char(c)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashSet;
+
+use crate::ast::{Inline, InlineKind};
+
+/// Gets Latin transliteration of lowercase Cyrillic letter
+fn transliterate(c: char) -> Option<&'static str> {
+    Some(match c {
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' => "g",
+        'д' => "d",
+        'е' | 'ё' | 'э' | 'є' => "e",
+        'ж' => "zh",
+        'з' => "z",
+        'и' | 'і' => "i",
+        'ї' => "yi",
+        'й' | 'ы' => "y",
+        'к' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' | 'ў' => "u",
+        'ф' => "f",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ъ' | 'ь' => "",
+        'ю' => "yu",
+        'я' => "ya",
+        'ґ' => "g",
+        _ => return None,
+    })
+}
+
+/// Makes slug from text: lowercase words joined by `-`, Cyrillic is
+/// transliterated. Returns `section` if text has no letters or digits
+///
+/// ```
+/// use fmf::slug::slugify;
+///
+/// assert_eq!(slugify("Hello, World!"), "hello-world");
+/// assert_eq!(slugify("Щука и ёж"), "shchuka-i-ezh");
+/// assert_eq!(slugify("???"), "section");
+/// ```
+pub fn slugify(v: &str) -> String {
+    let mut slug = String::new();
+    let mut gap = false;
+    for c in v.chars().flat_map(char::to_lowercase) {
+        if let Some(t) = transliterate(c) {
+            if gap && !t.is_empty() && !slug.is_empty() {
+                slug.push('-');
+            }
+            gap &= t.is_empty();
+            slug.push_str(t);
+        } else if c.is_alphanumeric() {
+            if gap && !slug.is_empty() {
+                slug.push('-');
+            }
+            gap = false;
+            slug.push(c);
+        } else {
+            gap = true;
+        }
+    }
+    match slug.is_empty() {
+        true => "section".to_owned(),
+        false => slug,
+    }
+}
+
+/// Gets text of inline nodes without formatting. Links are replaced by
+/// their text (or url), images by alt text and footnote references are skipped
+pub fn plain_text(nodes: &[Inline<'_>]) -> String {
+    fn push(out: &mut String, nodes: &[Inline<'_>]) {
+        for node in nodes {
+            match &node.kind {
+                InlineKind::Text(v) => out.push_str(v),
+                InlineKind::SoftBreak => out.push(' '),
+                InlineKind::Bold(v)
+                | InlineKind::Italic(v)
+                | InlineKind::Code(v)
+                | InlineKind::Strikethrough(v)
+                | InlineKind::Underline(v)
+                | InlineKind::Highlight(v)
+                | InlineKind::Subscript(v)
+                | InlineKind::Superscript(v) => push(out, v),
+                InlineKind::Keyboard(v) => out.push_str(v),
+                InlineKind::Link {
+                    text: Some(text), ..
+                } => push(out, text),
+                InlineKind::Link { url, text: None } => out.push_str(url.unwrap_or_default()),
+                InlineKind::Image { alt, .. } => out.push_str(alt.unwrap_or_default()),
+                InlineKind::FootnoteReference(_) => {}
+            }
+        }
+    }
+    let mut out = String::new();
+    push(&mut out, nodes);
+    out
+}
+
+/// Set of used slugs. Repeated slugs get a number suffix
+#[derive(Default)]
+pub(crate) struct Slugs(HashSet<String>);
+
+impl Slugs {
+    /// Gets unique slug of text: `foo`, `foo-1`, `foo-2`...
+    pub(crate) fn unique(&mut self, v: &str) -> String {
+        let slug = slugify(v);
+        let mut id = slug.clone();
+        let mut n = 0;
+        while self.0.contains(&id) {
+            n += 1;
+            id = format!("{slug}-{n}");
+        }
+        self.0.insert(id.clone());
+        id
+    }
+}
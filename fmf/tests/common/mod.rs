@@ -0,0 +1,75 @@
+use proptest::prelude::*;
+
+/// Source fragments that are glued into random documents
+const FRAGMENTS: &[&str] = &[
+    "\n",
+    "\n\n",
+    " ",
+    "  ",
+    "\n  - ",
+    "\n    @ ",
+    "\n> ",
+    "\n:::warning ",
+    "\n:::",
+    "-- ",
+    "\\!(",
+    "!",
+    "\\^[",
+    "\\~[",
+    "\\'[",
+    "\\<",
+    "\n^1: ",
+    "^",
+    "|",
+    "\n|-|",
+    "\n| :-: |",
+    "\n  ```",
+    "\n\n  ",
+    "\t",
+    "text",
+    "фыр",
+    "#",
+    "###",
+    "#######",
+    "- ",
+    "@ ",
+    "```",
+    "```lang",
+    "````",
+    "~~~",
+    "```file: a.rs, lang: ",
+    ", highlight: 1-2",
+    ", lines: true",
+    ", start: 3",
+    ", diff: true",
+    ", raw: true",
+    "\n+",
+    "\n-",
+    "```rust",
+    "```console",
+    "/*",
+    "$ ",
+    "'",
+    "/// ",
+    " / ",
+    "//!",
+    "*",
+    "_",
+    "`",
+    "\\",
+    "(",
+    ")",
+    "[",
+    "]",
+    "\\(",
+    "\\[",
+    "<",
+    ">",
+    "&",
+    "\"",
+];
+
+/// Random FMF document
+pub fn document() -> impl Strategy<Value = String> {
+    prop::collection::vec(prop::sample::select(FRAGMENTS), 0..64).prop_map(|v| v.concat())
+}
@@ -0,0 +1,99 @@
+use fmf::{
+    diagnostic::DiagnosticKind,
+    document::Document,
+    format::{DefaultFormatEngine, FormatType},
+};
+
+/// Gets diagnostics as kind and `line:column` of their start
+fn diagnostics(v: &str) -> Vec<(DiagnosticKind, usize, usize)> {
+    Document::<DefaultFormatEngine>::new(v)
+        .diagnostics()
+        .into_iter()
+        .map(|d| (d.kind, d.span.start.line, d.span.start.column))
+        .collect()
+}
+
+#[test]
+fn unclosed_code_block() {
+    assert_eq!(
+        diagnostics("text\n\n```rust\nfn main() {}\n"),
+        [(DiagnosticKind::UnclosedCodeBlock, 3, 1)]
+    );
+}
+
+#[test]
+fn unclosed_bold() {
+    assert_eq!(
+        diagnostics("some *bold text"),
+        [(DiagnosticKind::UnclosedFormat(FormatType::InlineBold), 1, 6)]
+    );
+}
+
+#[test]
+fn unclosed_italic() {
+    assert_eq!(
+        diagnostics("first line\nsome _italic"),
+        [(
+            DiagnosticKind::UnclosedFormat(FormatType::InlineItalic),
+            2,
+            6
+        )]
+    );
+}
+
+#[test]
+fn unclosed_code() {
+    assert_eq!(
+        diagnostics("- item `code"),
+        [(DiagnosticKind::UnclosedFormat(FormatType::InlineCode), 1, 8)]
+    );
+}
+
+#[test]
+fn unclosed_link() {
+    assert_eq!(
+        diagnostics("see \\(https://example.com"),
+        [(DiagnosticKind::UnclosedLink, 1, 5)]
+    );
+}
+
+#[test]
+fn invalid_author_card() {
+    assert_eq!(
+        diagnostics("# Title\n/// name / date"),
+        [(DiagnosticKind::InvalidAuthorCard(2), 2, 1)]
+    );
+}
+
+#[test]
+fn heading_too_deep() {
+    assert_eq!(
+        diagnostics("text\n\n####### deep"),
+        [(DiagnosticKind::HeadingTooDeep(7), 3, 1)]
+    );
+}
+
+#[test]
+fn unused_footnote() {
+    // Unused definition ends the previous one
+    assert_eq!(
+        diagnostics("Text\\^[3]\n\n^3: three\n^9: unused"),
+        [(DiagnosticKind::UnusedFootnote, 4, 1)]
+    );
+    assert_eq!(
+        diagnostics("Text\\^[3]\n\n^3: three\n\n^9: unused"),
+        [(DiagnosticKind::UnusedFootnote, 5, 1)]
+    );
+}
+
+#[test]
+fn swallowed_escape() {
+    assert_eq!(
+        diagnostics("```\narr[i\\] = 1;\nprintf(\"\\n\");\n```"),
+        [
+            (DiagnosticKind::SwallowedEscape, 2, 6),
+            (DiagnosticKind::SwallowedEscape, 3, 9)
+        ]
+    );
+    assert_eq!(diagnostics("```raw: true\narr[i\\] = 1;\n```"), []);
+}
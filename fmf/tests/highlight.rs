@@ -0,0 +1,302 @@
+use fmf::{format::TokenKind, highlight::Highlighter};
+
+/// Splits lines into tokens
+fn tokens<'a>(lang: &str, lines: &[&'a str]) -> Vec<Vec<(Option<TokenKind>, &'a str)>> {
+    let mut highlighter = Highlighter::new(lang).expect("language is supported");
+    lines
+        .iter()
+        .map(|line| {
+            let tokens = highlighter.tokens(line);
+            highlighter.end_line();
+            tokens
+        })
+        .collect()
+}
+
+#[test]
+fn c_comments_and_literals() {
+    // Block comment continues on the next line
+    assert_eq!(
+        tokens(
+            "c",
+            &[
+                "#include <stdio.h>",
+                "int main(void) { /* start",
+                "still */ return 0; }",
+                "char c = 'x'; // done",
+                "printf(\"%d\\n\", NULL);",
+            ]
+        ),
+        [
+            vec![
+                (Some(TokenKind::Attribute), "#include"),
+                (None, " <stdio.h>"),
+            ],
+            vec![
+                (Some(TokenKind::Type), "int"),
+                (None, " "),
+                (Some(TokenKind::Function), "main"),
+                (None, "("),
+                (Some(TokenKind::Type), "void"),
+                (None, ") { "),
+                (Some(TokenKind::Comment), "/* start"),
+            ],
+            vec![
+                (Some(TokenKind::Comment), "still */"),
+                (None, " "),
+                (Some(TokenKind::Keyword), "return"),
+                (None, " "),
+                (Some(TokenKind::Number), "0"),
+                (None, "; }"),
+            ],
+            vec![
+                (Some(TokenKind::Type), "char"),
+                (None, " c = "),
+                (Some(TokenKind::String), "'x'"),
+                (None, "; "),
+                (Some(TokenKind::Comment), "// done"),
+            ],
+            vec![
+                (Some(TokenKind::Function), "printf"),
+                (None, "("),
+                (Some(TokenKind::String), "\"%d\\n\""),
+                (None, ", "),
+                (Some(TokenKind::Constant), "NULL"),
+                (None, ");"),
+            ],
+        ]
+    );
+}
+
+#[test]
+fn toml_tables_keys_and_strings() {
+    // Tables are types, keys can be dotted, dashed or quoted
+    assert_eq!(
+        tokens(
+            "toml",
+            &[
+                "[server.http]",
+                "bind-addr = \"0.0.0.0\"",
+                "a.b-c = 1.5 # comment",
+                "s = '''",
+                "multi",
+                "line''' ",
+                "[[bin]]",
+                "\"quoted key\" = true",
+            ]
+        ),
+        [
+            vec![(Some(TokenKind::Type), "[server.http]"),],
+            vec![
+                (Some(TokenKind::Key), "bind-addr"),
+                (None, " = "),
+                (Some(TokenKind::String), "\"0.0.0.0\""),
+            ],
+            vec![
+                (Some(TokenKind::Key), "a.b-c"),
+                (None, " = "),
+                (Some(TokenKind::Number), "1.5"),
+                (None, " "),
+                (Some(TokenKind::Comment), "# comment"),
+            ],
+            vec![
+                (Some(TokenKind::Key), "s"),
+                (None, " = "),
+                (Some(TokenKind::String), "'''"),
+            ],
+            vec![(Some(TokenKind::String), "multi"),],
+            vec![(Some(TokenKind::String), "line'''"), (None, " "),],
+            vec![(Some(TokenKind::Type), "[[bin]]"),],
+            vec![
+                (Some(TokenKind::Key), "\"quoted key\""),
+                (None, " = "),
+                (Some(TokenKind::Constant), "true"),
+            ],
+        ]
+    );
+}
+
+#[test]
+fn json_keys() {
+    assert_eq!(
+        tokens(
+            "json",
+            &[
+                "{\"name\": \"fmf\", \"n\": 10,",
+                "\"ok\": true, \"none\": null}",
+            ]
+        ),
+        [
+            vec![
+                (None, "{"),
+                (Some(TokenKind::Key), "\"name\""),
+                (None, ": "),
+                (Some(TokenKind::String), "\"fmf\""),
+                (None, ", "),
+                (Some(TokenKind::Key), "\"n\""),
+                (None, ": "),
+                (Some(TokenKind::Number), "10"),
+                (None, ","),
+            ],
+            vec![
+                (Some(TokenKind::Key), "\"ok\""),
+                (None, ": "),
+                (Some(TokenKind::Constant), "true"),
+                (None, ", "),
+                (Some(TokenKind::Key), "\"none\""),
+                (None, ": "),
+                (Some(TokenKind::Constant), "null"),
+                (None, "}"),
+            ],
+        ]
+    );
+}
+
+#[test]
+fn shell_variables_and_comments() {
+    // `#` starts comment only after whitespace, strings can contain line breaks
+    assert_eq!(
+        tokens(
+            "sh",
+            &[
+                "echo $HOME ${PATH} $? # comment",
+                "url=a#b",
+                "for f in *; do",
+                "echo \"multi",
+                "line\"; done",
+            ]
+        ),
+        [
+            vec![
+                (None, "echo "),
+                (Some(TokenKind::Variable), "$HOME"),
+                (None, " "),
+                (Some(TokenKind::Variable), "${PATH}"),
+                (None, " "),
+                (Some(TokenKind::Variable), "$?"),
+                (None, " "),
+                (Some(TokenKind::Comment), "# comment"),
+            ],
+            vec![(None, "url=a#b"),],
+            vec![
+                (Some(TokenKind::Keyword), "for"),
+                (None, " f "),
+                (Some(TokenKind::Keyword), "in"),
+                (None, " *; "),
+                (Some(TokenKind::Keyword), "do"),
+            ],
+            vec![(None, "echo "), (Some(TokenKind::String), "\"multi"),],
+            vec![
+                (Some(TokenKind::String), "line\""),
+                (None, "; "),
+                (Some(TokenKind::Keyword), "done"),
+            ],
+        ]
+    );
+}
+
+#[test]
+fn console_prompt_and_output() {
+    // Lines without prompt are output and are not highlighted
+    assert_eq!(
+        tokens(
+            "console",
+            &["$ ls -la # list", "total 0", "# whoami", "root",]
+        ),
+        [
+            vec![
+                (Some(TokenKind::Prompt), "$"),
+                (None, " ls -la "),
+                (Some(TokenKind::Comment), "# list"),
+            ],
+            vec![(None, "total 0"),],
+            vec![(Some(TokenKind::Prompt), "#"), (None, " whoami"),],
+            vec![(None, "root"),],
+        ]
+    );
+}
+
+#[test]
+fn python_decorators_and_docstrings() {
+    assert_eq!(
+        tokens(
+            "python",
+            &[
+                "@dataclass",
+                "def f(x: int) -> None:",
+                "    s = \"\"\"doc",
+                "    more\"\"\"",
+                "    return True # yes",
+            ]
+        ),
+        [
+            vec![(Some(TokenKind::Attribute), "@dataclass"),],
+            vec![
+                (Some(TokenKind::Keyword), "def"),
+                (None, " "),
+                (Some(TokenKind::Function), "f"),
+                (None, "(x: "),
+                (Some(TokenKind::Type), "int"),
+                (None, ") -> "),
+                (Some(TokenKind::Constant), "None"),
+                (None, ":"),
+            ],
+            vec![(None, "    s = "), (Some(TokenKind::String), "\"\"\"doc"),],
+            vec![(Some(TokenKind::String), "    more\"\"\""),],
+            vec![
+                (None, "    "),
+                (Some(TokenKind::Keyword), "return"),
+                (None, " "),
+                (Some(TokenKind::Constant), "True"),
+                (None, " "),
+                (Some(TokenKind::Comment), "# yes"),
+            ],
+        ]
+    );
+}
+
+#[test]
+fn rust_multiline_tokens() {
+    // Lifetimes are not char literals
+    assert_eq!(
+        tokens(
+            "rust",
+            &[
+                "#[derive(Debug)]",
+                "struct A<'a> { s: &'a str }",
+                "let s = \"multi",
+                "line\"; /* a",
+                "b */ let c = '\\n';",
+            ]
+        ),
+        [
+            vec![(Some(TokenKind::Attribute), "#[derive(Debug)]"),],
+            vec![
+                (Some(TokenKind::Keyword), "struct"),
+                (None, " "),
+                (Some(TokenKind::Type), "A"),
+                (None, "<'a> { s: &'a "),
+                (Some(TokenKind::Type), "str"),
+                (None, " }"),
+            ],
+            vec![
+                (Some(TokenKind::Keyword), "let"),
+                (None, " s = "),
+                (Some(TokenKind::String), "\"multi"),
+            ],
+            vec![
+                (Some(TokenKind::String), "line\""),
+                (None, "; "),
+                (Some(TokenKind::Comment), "/* a"),
+            ],
+            vec![
+                (Some(TokenKind::Comment), "b */"),
+                (None, " "),
+                (Some(TokenKind::Keyword), "let"),
+                (None, " c = "),
+                (Some(TokenKind::String), "'\\n'"),
+                (None, ";"),
+            ],
+        ]
+    );
+}
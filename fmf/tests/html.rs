@@ -0,0 +1,159 @@
+use fmf::{document::Document, format::DefaultFormatEngine};
+
+fn html(v: &str) -> String {
+    Document::<DefaultFormatEngine>::new(v).to_string()
+}
+
+#[test]
+fn quote_attribution() {
+    assert_eq!(
+        html("> quoted text\n> -- Author Name"),
+        "<blockquote><p>quoted text\n</p><footer>Author Name</footer></blockquote>"
+    );
+}
+
+#[test]
+fn table_alignment() {
+    assert_eq!(
+        html("| a | b | c |\n|:--|:-:|--:|\n| 1 | 2 | 3 |"),
+        concat!(
+            "<div class=\"table\"><table><tr>",
+            "<th style=\"text-align: left\">a</th>",
+            "<th style=\"text-align: center\">b</th>",
+            "<th style=\"text-align: right\">c</th>",
+            "</tr><tr>",
+            "<td style=\"text-align: left\">1</td>",
+            "<td style=\"text-align: center\">2</td>",
+            "<td style=\"text-align: right\">3</td>",
+            "</tr></table></div>",
+        )
+    );
+}
+
+#[test]
+fn figure_caption() {
+    assert_eq!(
+        html("\\!(img.png)[alt text]\ncaption line"),
+        "<figure><img src=\"img.png\" alt=\"alt text\"><figcaption>caption line</figcaption></figure>"
+    );
+}
+
+#[test]
+fn footnote() {
+    assert_eq!(
+        html("\\^C: stops the program, ^D: EOF"),
+        "<p>^C: stops the program, ^D: EOF\n</p>"
+    );
+    assert_eq!(
+        html("Text\\^[ps]\n\n^ps: note"),
+        concat!(
+            "<p>Text<sup class=\"footnote\"><a id=\"fnref-1\" href=\"#fn-1\">1</a></sup>\n</p>",
+            "<section class=\"footnotes\"><ol><li id=\"fn-1\">",
+            "note <a class=\"footnote-back\" href=\"#fnref-1\">↩</a>",
+            "</li></ol></section>",
+        )
+    );
+}
+
+#[test]
+fn callout() {
+    assert_eq!(
+        html(":::warning Be careful\ntext\n\n- item\n:::"),
+        concat!(
+            "<div class=\"callout warning\"><div class=\"callout-title\">Be careful</div>",
+            "<p>text\n</p><ul><li>item\n</li></ul></div>",
+        )
+    );
+    assert_eq!(
+        html(":::tip\nx\n:::"),
+        "<div class=\"callout tip\"><p>x\n</p></div>"
+    );
+}
+
+#[test]
+fn code_line_numbers() {
+    assert_eq!(
+        html("```lines: true, start: 9, highlight: 10\na\nb\n```"),
+        concat!(
+            "<div class=\"codeblock\"><div class=\"prelude\"></div><pre>",
+            "<span class=\"line\"><span class=\"line-number\">9</span>a</span>\n",
+            "<span class=\"line highlighted\"><span class=\"line-number\">10</span>b</span>\n",
+            "</pre></div>",
+        )
+    );
+}
+
+#[test]
+fn code_highlighted_lines() {
+    assert_eq!(
+        html("```highlight: 2\na\nb\n```"),
+        concat!(
+            "<div class=\"codeblock\"><div class=\"prelude\"></div><pre>",
+            "<span class=\"line\">a</span>\n",
+            "<span class=\"line highlighted\">b</span>\n",
+            "</pre></div>",
+        )
+    );
+}
+
+#[test]
+fn diff_rows() {
+    assert_eq!(
+        html("```diff: true\n@@ -1 +1 @@\n-old\n+new\n same\n```"),
+        concat!(
+            "<div class=\"codeblock\"><div class=\"prelude\"></div><pre>",
+            "<span class=\"line diff-header\">@@ -1 +1 @@</span>\n",
+            "<span class=\"line removed\">-old</span>\n",
+            "<span class=\"line added\">+new</span>\n",
+            "<span class=\"line context\"> same</span>\n",
+            "</pre></div>",
+        )
+    );
+}
+
+#[test]
+fn highlighted_diff_rows() {
+    assert_eq!(
+        html("```diff: true, highlight: 1-2\n-old\n+new\n same\n```"),
+        concat!(
+            "<div class=\"codeblock\"><div class=\"prelude\"></div><pre>",
+            "<span class=\"line removed highlighted\">-old</span>\n",
+            "<span class=\"line added highlighted\">+new</span>\n",
+            "<span class=\"line context\"> same</span>\n",
+            "</pre></div>",
+        )
+    );
+}
+
+#[test]
+fn raw_code_block() {
+    assert_eq!(
+        html("```raw: true\nprintf(\"a\\n*b* <c>\");\n```"),
+        concat!(
+            "<div class=\"codeblock\"><div class=\"prelude\"></div><pre>",
+            "printf(\"a\\n*b* &lt;c&gt;\");\n",
+            "</pre></div>",
+        )
+    );
+    // Without `raw: true` the escape is removed
+    assert_eq!(
+        html("```\nprintf(\"a\\n\");\n```"),
+        "<div class=\"codeblock\"><div class=\"prelude\"></div><pre>printf(\"an\");\n</pre></div>"
+    );
+    assert_eq!(
+        html("```raw: true\narr[i\\] = 1;\n```"),
+        "<div class=\"codeblock\"><div class=\"prelude\"></div><pre>arr[i\\] = 1;\n</pre></div>"
+    );
+}
+
+#[test]
+fn unused_footnote_is_not_rendered() {
+    let footnotes = concat!(
+        "<p>Text<sup class=\"footnote\"><a id=\"fnref-1\" href=\"#fn-1\">1</a></sup>\n</p>",
+        "<section class=\"footnotes\"><ol><li id=\"fn-1\">",
+        "three <a class=\"footnote-back\" href=\"#fnref-1\">↩</a>",
+        "</li></ol></section>",
+    );
+    assert_eq!(html("Text\\^[3]\n\n^3: three\n^9: unused"), footnotes);
+    assert_eq!(html("Text\\^[3]\n\n^3: three\n\n^9: unused"), footnotes);
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use fmf::{
+    diagnostic::DiagnosticKind,
+    document::Document,
+    format::DefaultFormatEngine,
+    include::{expand, MAX_DEPTH},
+};
+
+fn files(v: &[(&str, &str)]) -> HashMap<String, String> {
+    v.iter()
+        .map(|&(name, v)| (name.to_owned(), v.to_owned()))
+        .collect()
+}
+
+#[test]
+fn include_after_paragraph() {
+    let mut files = files(&[("footer.fmf", "Thanks!")]);
+    let expanded = expand(
+        "post.fmf",
+        "Text\n//! include: footer.fmf\nMore",
+        &mut files,
+    );
+    assert_eq!(expanded.v, "Text\n\nThanks!\n\nMore");
+    assert!(expanded.diagnostics.is_empty());
+}
+
+#[test]
+fn include_after_list_item() {
+    let mut files = files(&[("footer.fmf", "Thanks!")]);
+    let expanded = expand("post.fmf", "- item\n//! include: footer.fmf", &mut files);
+    assert_eq!(expanded.v, "- item\n\nThanks!\n");
+}
+
+#[test]
+fn include_depth_limit() {
+    // Every document includes the next one, so the last include is too deep
+    let chain = (0..MAX_DEPTH + 2)
+        .map(|i| {
+            (
+                format!("{i}.fmf"),
+                format!("{i}\n\n//! include: {}.fmf", i + 1),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+    let mut files = chain.clone();
+    let expanded = expand("0.fmf", &chain["0.fmf"], &mut files);
+    let [(name, diagnostic)] = &expanded.diagnostics[..] else {
+        panic!("expected single diagnostic: {:?}", expanded.diagnostics);
+    };
+    assert_eq!(diagnostic.kind, DiagnosticKind::IncludeTooDeep);
+    assert_eq!(name, &format!("{MAX_DEPTH}.fmf"));
+    assert_eq!(
+        (diagnostic.span.start.line, diagnostic.span.start.column),
+        (3, 1)
+    );
+}
+
+#[test]
+fn include_inside_callout() {
+    let mut files = files(&[("tip.fmf", "Use *fmt*.")]);
+    let expanded = expand("post.fmf", ":::tip\n//! include: tip.fmf\n:::", &mut files);
+    assert_eq!(expanded.v, ":::tip\n\nUse *fmt*.\n\n:::");
+    assert!(expanded.diagnostics.is_empty());
+    assert_eq!(
+        Document::<DefaultFormatEngine>::new(&expanded.v).to_string(),
+        "<div class=\"callout tip\"><p>Use <b>fmt</b>.\n</p></div>"
+    );
+}
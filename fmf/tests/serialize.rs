@@ -0,0 +1,85 @@
+use fmf::{document::Document, serialize::Fmf};
+use proptest::prelude::*;
+
+mod common;
+use common::document;
+
+fn format(v: &str) -> String {
+    Fmf(&Document::<fmf::format::DefaultFormatEngine>::new(v).parse()).to_string()
+}
+
+/// Checks that formatted document is rendered the same
+fn assert_keeps_output(v: &str) {
+    let formatted = format(v);
+    assert_eq!(
+        Document::<fmf::format::DefaultFormatEngine>::new(v).to_string(),
+        Document::<fmf::format::DefaultFormatEngine>::new(&formatted).to_string(),
+        "formatted: {formatted:?}"
+    );
+}
+
+#[test]
+fn split_author_card_is_escaped() {
+    assert_keeps_output("/\\///'/");
+}
+
+#[test]
+fn split_metadata_is_escaped() {
+    assert_keeps_output("/\\//!x");
+}
+
+#[test]
+fn tilde_fence_is_escaped() {
+    assert_keeps_output("\\~~~");
+    assert_eq!(format(&format("\\~~~")), format("\\~~~"));
+}
+
+#[test]
+fn invalid_author_card_is_kept() {
+    for v in [
+        "- a\n\n/// b\n\n- c",
+        "- a\n///\n- c",
+        "text\n\n/// a / b / c / d",
+    ] {
+        assert_keeps_output(v);
+        assert_eq!(format(&format(v)), format(v));
+    }
+    assert_eq!(format("- a\n\n///  b/c\n\n- d"), "- a\n\n/// b/c\n\n- d\n");
+}
+
+#[test]
+fn metadata_after_break_is_escaped() {
+    for v in [
+        "text\n\\//! a: b",
+        "\\!(a.png)[a]\n\\//! a: b",
+        "- a\n\n  b\n  \\//!",
+    ] {
+        assert_keeps_output(v);
+    }
+}
+
+#[test]
+fn intraword_underscores_are_not_escaped() {
+    assert_eq!(format("snake_case_names"), "snake_case_names\n");
+    assert_eq!(format("_italic_ and a_b"), "_italic_ and a_b\n");
+    assert_keeps_output("_a_b_ c_ _d");
+}
+
+proptest! {
+    #[test]
+    fn formatting_keeps_output(v in document()) {
+        let formatted = format(&v);
+        prop_assert_eq!(
+            Document::<fmf::format::DefaultFormatEngine>::new(&v).to_string(),
+            Document::<fmf::format::DefaultFormatEngine>::new(&formatted).to_string(),
+            "formatted: {:?}",
+            formatted
+        );
+    }
+
+    #[test]
+    fn formatting_is_idempotent(v in document()) {
+        let formatted = format(&v);
+        prop_assert_eq!(format(&formatted), formatted);
+    }
+}
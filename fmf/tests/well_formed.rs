@@ -0,0 +1,81 @@
+use fmf::{
+    document::Document,
+    format::{CodeblockEngine, FormatEngine, ParagraphEngine},
+    parser::{push_html, push_toc, Parser},
+};
+use proptest::prelude::*;
+
+mod common;
+use common::document;
+
+/// Checks that every opened tag is closed in the right order
+fn check_balance(html: &str) -> Result<(), String> {
+    let mut stack = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        let tag = &rest[start + 1..];
+        let end = tag
+            .find('>')
+            .ok_or_else(|| format!("unterminated tag in {html:?}"))?;
+        rest = &tag[end + 1..];
+
+        let (closing, tag) = match tag[..end].strip_prefix('/') {
+            Some(tag) => (true, tag),
+            None => (false, &tag[..end]),
+        };
+        let name = tag.split_whitespace().next().unwrap_or_default();
+        match (closing, name) {
+            (false, "img") => {}
+            (false, name) => stack.push(name),
+            (true, name) if stack.pop() == Some(name) => {}
+            (true, name) => return Err(format!("unexpected </{name}> in {html:?}")),
+        }
+    }
+    match stack.is_empty() {
+        true => Ok(()),
+        false => Err(format!("unclosed tags {stack:?} in {html:?}")),
+    }
+}
+
+fn render<E: FormatEngine>(v: &str) -> String {
+    Document::<E>::new(v).to_string()
+}
+
+proptest! {
+    #[test]
+    fn paragraph_engine_is_balanced(v in document()) {
+        prop_assert_eq!(check_balance(&render::<ParagraphEngine>(&v)), Ok(()));
+    }
+
+    #[test]
+    fn codeblock_engine_is_balanced(v in document()) {
+        prop_assert_eq!(check_balance(&render::<CodeblockEngine>(&v)), Ok(()));
+    }
+
+    #[test]
+    fn filtered_events_are_balanced(v in document(), mask in any::<u64>()) {
+        let events = Parser::<ParagraphEngine>::new(&v)
+            .enumerate()
+            .filter(|(idx, _)| mask & (1 << (idx % 64)) != 0)
+            .map(|(_, e)| e);
+        let mut html = String::new();
+        push_html::<ParagraphEngine>(&mut html, events).unwrap();
+        prop_assert_eq!(check_balance(&html), Ok(()));
+    }
+
+    #[test]
+    fn anchors_are_balanced(v in document()) {
+        let mut html = String::new();
+        push_html::<ParagraphEngine>(&mut html, Parser::<ParagraphEngine>::new(&v).anchors(true))
+            .unwrap();
+        prop_assert_eq!(check_balance(&html), Ok(()));
+    }
+
+    #[test]
+    fn toc_is_balanced(v in document()) {
+        let mut html = String::new();
+        push_toc::<ParagraphEngine>(&mut html, &Document::<ParagraphEngine>::new(&v).outline())
+            .unwrap();
+        prop_assert_eq!(check_balance(&html), Ok(()));
+    }
+}
@@ -1,7 +1,13 @@
-use std::{fmt::Display, fs, io::Write, process::ExitCode};
+use std::{collections::BTreeMap, fmt::Display, fs, io::Write, process::ExitCode};
 
 use clap::{Parser, Subcommand};
-use fmf::{document::Document, format::DefaultFormatEngine};
+use fmf::{
+    document::Document,
+    format::DefaultFormatEngine,
+    include::{expand, FsResolver},
+    parser::{push_html, push_toc},
+    serialize::Fmf,
+};
 use serde::Serialize;
 
 /// FMF (field, millet, fox) format compiler and project manager
@@ -33,25 +39,49 @@ enum Commands {
         #[arg(long)]
         template: Option<String>,
 
+        /// Add links to headings themselves
+        #[arg(long)]
+        anchors: bool,
+
         /// Output filename (default to stdout)
         #[arg(short, long)]
         output: Option<String>,
     },
+    /// Rewrite documents in canonical layout
+    Fmt {
+        /// Documents
+        #[arg(required = true)]
+        filenames: Vec<String>,
+
+        /// Do not write files, fail if any document is not formatted
+        #[arg(long)]
+        check: bool,
+    },
 }
 
 #[derive(Serialize)]
 struct QueryData<'a> {
     title: &'a str,
+    /// The first author, kept for older consumers of json output
     author: Option<&'a str>,
+    /// Authors of all author cards
+    authors: Vec<&'a str>,
     date: Option<&'a str>,
     category: Option<&'a str>,
+    /// `//! key: value` lines
+    metadata: BTreeMap<&'a str, &'a str>,
 }
 
 impl<'a> Display for QueryData<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Title: {}", self.title)?;
-        if let Some(author) = self.author {
-            writeln!(f, "\n\nAuthor: {author}")?;
+        if !self.authors.is_empty() || self.date.is_some() {
+            f.write_str("\n\n")?;
+        }
+        match self.authors[..] {
+            [] => {}
+            [author] => writeln!(f, "Author: {author}")?,
+            ref authors => writeln!(f, "Authors: {}", authors.join(", "))?,
         }
         if let Some(date) = self.date {
             writeln!(f, "Date: {date}")?;
@@ -59,6 +89,12 @@ impl<'a> Display for QueryData<'a> {
         if let Some(category) = self.category {
             write!(f, "Category: {category}")?;
         }
+        if !self.metadata.is_empty() {
+            f.write_str("\n")?;
+        }
+        for (key, value) in &self.metadata {
+            write!(f, "\n{key}: {value}")?;
+        }
 
         Ok(())
     }
@@ -71,25 +107,44 @@ fn main() -> ExitCode {
         Commands::Cc {
             filename,
             template,
+            anchors,
             output,
         } => {
-            let s = match fs::read_to_string(filename) {
+            let s = match fs::read_to_string(&filename) {
                 Ok(v) => v,
                 Err(e) => {
                     eprintln!("Failed to read file: {e}");
                     return ExitCode::FAILURE;
                 }
             };
-            let doc = Document::<DefaultFormatEngine>::new(&s);
+            let expanded = expand(&filename, &s, &mut FsResolver);
+            let doc = Document::<DefaultFormatEngine>::new(&expanded.v);
+            for (name, diagnostic) in &expanded.diagnostics {
+                eprintln!("{name}:{diagnostic}");
+            }
+            for diagnostic in doc.diagnostics() {
+                let (name, diagnostic) = expanded.locate_diagnostic(diagnostic);
+                eprintln!("{name}:{diagnostic}");
+            }
+            let mut out = String::new();
+            push_html::<DefaultFormatEngine>(&mut out, doc.parser().anchors(anchors))
+                .expect("formatting into string");
             let out = if let Some(template) = template {
-                let out = format!("{doc}");
                 let title = doc.get_title();
-                let (author, date, category) = doc.get_author_card().unwrap_or_default();
+                let (_, date, category) = doc.get_author_card().unwrap_or_default();
+                let authors = doc.authors().join(", ");
+                // Title is not a part of contents
+                let mut outline = doc.outline();
+                outline.retain(|heading| heading.level > 1);
+                let mut toc = String::new();
+                push_toc::<DefaultFormatEngine>(&mut toc, &outline)
+                    .expect("formatting into string");
                 match fs::read_to_string(template) {
                     Ok(v) => v
                         .replace("{contents}", &out)
+                        .replace("{toc}", &toc)
                         .replace("{title}", title)
-                        .replace("{author}", author)
+                        .replace("{author}", &authors)
                         .replace("{date}", date)
                         .replace("{category}", category),
                     Err(e) => {
@@ -98,7 +153,7 @@ fn main() -> ExitCode {
                     }
                 }
             } else {
-                format!("{doc}")
+                out
             };
             if let Some(output) = output {
                 if let Err(e) | Ok(Err(e)) =
@@ -112,6 +167,43 @@ fn main() -> ExitCode {
             }
         }
 
+        Commands::Fmt { filenames, check } => {
+            let mut code = ExitCode::SUCCESS;
+            for filename in filenames {
+                let s = match fs::read_to_string(&filename) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("Failed to read file: {e}");
+                        return ExitCode::FAILURE;
+                    }
+                };
+                let doc = Document::<DefaultFormatEngine>::new(&s);
+                // Some malformed parts (like unknown callout kinds) are not kept
+                // in syntax tree, so formatting would lose them
+                let diagnostics = doc.diagnostics();
+                if diagnostics.iter().any(|d| d.kind.is_lossy()) {
+                    for diagnostic in diagnostics.iter().filter(|d| d.kind.is_lossy()) {
+                        eprintln!("{filename}:{diagnostic}");
+                    }
+                    eprintln!("Skipping {filename}: fix warnings first");
+                    code = ExitCode::FAILURE;
+                    continue;
+                }
+                let out = Fmf(&doc.parse()).to_string();
+                if out == s {
+                    continue;
+                }
+                if check {
+                    println!("Not formatted: {filename}");
+                    code = ExitCode::FAILURE;
+                } else if let Err(e) = fs::write(&filename, out) {
+                    eprintln!("Failed to write to file: {e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+            return code;
+        }
+
         Commands::Query { filename, json } => {
             let s = match fs::read_to_string(filename) {
                 Ok(v) => v,
@@ -123,15 +215,18 @@ fn main() -> ExitCode {
             let doc = Document::<DefaultFormatEngine>::new(&s);
             let query = {
                 let title = doc.get_title();
-                let (author, date, category) = match doc.get_author_card() {
-                    Some((a, b, c)) => (Some(a), Some(b), Some(c)),
-                    None => (None, None, None),
+                let (date, category) = match doc.get_author_card() {
+                    Some((_, date, category)) => (Some(date), Some(category)),
+                    None => (None, None),
                 };
+                let authors = doc.authors();
                 QueryData {
                     title,
-                    author,
+                    author: authors.first().copied(),
+                    authors,
                     date,
                     category,
+                    metadata: doc.metadata(),
                 }
             };
             if json {